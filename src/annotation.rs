@@ -0,0 +1,159 @@
+use bidirected_adjacency_array::index::GraphIndexInteger;
+
+use crate::{
+    dijkstra::{SearchTargets, multi_shortest_paths},
+    graph::{SequenceGraph, SwappedOverlaps},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A set of labelled locations in a graph that can be queried for the annotations closest to a location.
+///
+/// Annotations are oriented: an annotation on a directed node is only found by paths through that orientation.
+/// To annotate both strands, register the location together with its inversion.
+/// The index precomputes the thresholds that the searches derive from the annotations, so queries only search from the query location.
+pub struct AnnotationIndex<IndexType, LengthType, Label> {
+    labels: Vec<Label>,
+    locations: Vec<GfaLocation<IndexType, LengthType>>,
+    /// The annotated locations as targets of downstream searches.
+    forward_targets: SearchTargets<IndexType, LengthType>,
+    /// The inverted annotated locations as targets of upstream searches, which run in [`SwappedOverlaps`].
+    inverted_targets: SearchTargets<IndexType, LengthType>,
+}
+
+/// The direction in which to search for annotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchDirection {
+    /// Find annotations that can be reached from the query location.
+    Downstream,
+
+    /// Find annotations from which the query location can be reached.
+    Upstream,
+}
+
+/// An annotation found by a nearest annotation query.
+pub struct AnnotationHit<'index, IndexType, LengthType, Label> {
+    label: &'index Label,
+    location: GfaLocation<IndexType, LengthType>,
+    distance: GfaPathLength<LengthType>,
+    path: Option<GfaPath<IndexType, LengthType>>,
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger, Label>
+    AnnotationIndex<IndexType, LengthType, Label>
+{
+    /// Index the annotations.
+    ///
+    /// Returns an error if a threshold of the annotations does not fit into the length type.
+    pub fn new(
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        annotations: impl IntoIterator<Item = (GfaLocation<IndexType, LengthType>, Label)>,
+    ) -> Result<Self, LengthOverflowError> {
+        let (locations, labels): (Vec<_>, Vec<_>) = annotations.into_iter().unzip();
+        let forward_targets =
+            SearchTargets::new(graph, locations.iter().copied().map(Into::into).collect())?;
+        let inverted_targets = SearchTargets::new(
+            &SwappedOverlaps::new(graph),
+            locations
+                .iter()
+                .map(|location| location.invert(graph).into())
+                .collect(),
+        )?;
+
+        Ok(Self {
+            labels,
+            locations,
+            forward_targets,
+            inverted_targets,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Find the `k` annotations closest to `location` in the given direction.
+    ///
+    /// Only annotations with a distance of at most `max_distance` are returned, if given.
+    /// The hits are ordered by increasing distance.
+    /// If `with_paths` is set, each hit carries a shortest path between the query location and the annotation.
+    /// Upstream paths start at the annotation and end at the query location.
+    /// Upstream hits are found by searching downstream from the inverted query location with the tail and head overlaps of all edges swapped,
+    /// so that the length of a walk is the length of its reverse complement, i.e. of the path from the annotation to the query location.
+    /// The graph must be the graph that the index was built for.
+    /// Returns an error if a distance of the search does not fit into the length type.
+    pub fn nearest(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        location: GfaLocation<IndexType, LengthType>,
+        direction: SearchDirection,
+        k: usize,
+        max_distance: Option<GfaPathLength<LengthType>>,
+        with_paths: bool,
+    ) -> Result<Vec<AnnotationHit<'_, IndexType, LengthType, Label>>, LengthOverflowError> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let results = match direction {
+            SearchDirection::Downstream => multi_shortest_paths(
+                graph,
+                &[location.into()],
+                &self.forward_targets,
+                k,
+                max_distance,
+            )?,
+            SearchDirection::Upstream => multi_shortest_paths(
+                &SwappedOverlaps::new(graph),
+                &[location.invert(graph).into()],
+                &self.inverted_targets,
+                k,
+                max_distance,
+            )?,
+        };
+
+        Ok(results
+            .into_iter()
+            .map(|result| AnnotationHit {
+                label: &self.labels[result.target_index],
+                location: self.locations[result.target_index],
+                distance: result.length(),
+                path: with_paths.then(|| match direction {
+                    SearchDirection::Downstream => result.path(graph),
+                    SearchDirection::Upstream => result.swapped_inverted_path(graph),
+                }),
+            })
+            .collect())
+    }
+}
+
+impl<'index, IndexType: GraphIndexInteger, LengthType: GraphIndexInteger, Label>
+    AnnotationHit<'index, IndexType, LengthType, Label>
+{
+    pub fn label(&self) -> &'index Label {
+        self.label
+    }
+
+    pub fn location(&self) -> GfaLocation<IndexType, LengthType> {
+        self.location
+    }
+
+    pub fn distance(&self) -> GfaPathLength<LengthType> {
+        self.distance
+    }
+
+    pub fn path(&self) -> Option<&GfaPath<IndexType, LengthType>> {
+        self.path.as_ref()
+    }
+
+    pub fn into_path(self) -> Option<GfaPath<IndexType, LengthType>> {
+        self.path
+    }
+}
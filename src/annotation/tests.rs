@@ -0,0 +1,237 @@
+use bidirected_adjacency_array::{index::DirectedNodeIndex, io::gfa1::read_gfa1};
+
+use crate::{
+    annotation::{AnnotationIndex, SearchDirection},
+    io::{LengthMismatchPolicy, UnknownOverlapPolicy, read_gfa1_lengths},
+    location::GfaLocation,
+    path::PathElement,
+};
+
+const CHAIN_GFA: &str = "H\tVN:Z:1.0
+S\tA\tAAA
+S\tB\tCCC
+S\tC\tGGGG
+L\tA\t+\tB\t+\t0M
+L\tB\t+\tC\t+\t0M
+";
+
+#[test]
+fn test_nearest_downstream() {
+    let graph = read_gfa1::<u8>(&mut CHAIN_GFA.as_bytes()).unwrap();
+    let index = AnnotationIndex::new(
+        &graph,
+        [
+            (GfaLocation::new(0.into(), 1.into()), "a"),
+            (GfaLocation::new(2.into(), 1.into()), "b"),
+            (GfaLocation::new(4.into(), 2.into()), "c"),
+            (GfaLocation::new(3.into(), 1.into()), "b-reverse"),
+        ],
+    )
+    .unwrap();
+    let query = GfaLocation::new(DirectedNodeIndex::new(0), 2.into());
+
+    let hits = index
+        .nearest(&graph, query, SearchDirection::Downstream, 3, None, true)
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["b", "c"],
+    );
+    assert_eq!(
+        hits.iter()
+            .map(|hit| hit.distance().into_usize())
+            .collect::<Vec<_>>(),
+        [2, 6],
+    );
+    assert_eq!(
+        hits[0].path().unwrap().iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 2.into(), 3.into()),
+            PathElement::new(2.into(), 0.into(), 1.into()),
+        ],
+    );
+
+    let hits = index
+        .nearest(&graph, query, SearchDirection::Downstream, 1, None, false)
+        .unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(*hits[0].label(), "b");
+    assert!(hits[0].path().is_none());
+
+    let hits = index
+        .nearest(
+            &graph,
+            query,
+            SearchDirection::Downstream,
+            3,
+            Some(5.into()),
+            false,
+        )
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["b"],
+    );
+}
+
+#[test]
+fn test_nearest_upstream() {
+    let graph = read_gfa1::<u8>(&mut CHAIN_GFA.as_bytes()).unwrap();
+    let index = AnnotationIndex::new(
+        &graph,
+        [
+            (GfaLocation::new(0.into(), 1.into()), "a"),
+            (GfaLocation::new(2.into(), 1.into()), "b"),
+            (GfaLocation::new(3.into(), 1.into()), "b-reverse"),
+        ],
+    )
+    .unwrap();
+    let query = GfaLocation::new(DirectedNodeIndex::new(4), 1.into());
+
+    let hits = index
+        .nearest(&graph, query, SearchDirection::Upstream, 5, None, true)
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["b", "a"],
+    );
+    assert_eq!(
+        hits.iter()
+            .map(|hit| hit.distance().into_usize())
+            .collect::<Vec<_>>(),
+        [3, 6],
+    );
+    assert_eq!(
+        hits[0].path().unwrap().iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(2.into(), 1.into(), 3.into()),
+            PathElement::new(4.into(), 0.into(), 1.into()),
+        ],
+    );
+}
+
+#[test]
+fn test_nearest_with_long_annotated_node() {
+    let gfa = "S\tQ\t*\tLN:i:3
+S\tL\t*\tLN:i:1000
+S\tB\t*\tLN:i:3
+S\tC\t*\tLN:i:5
+L\tQ\t+\tL\t+\t0M
+L\tQ\t+\tB\t+\t0M
+L\tB\t+\tC\t+\t0M
+";
+    let graph = read_gfa1_lengths::<u8>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    let index = AnnotationIndex::new(
+        &graph,
+        [
+            (GfaLocation::new(2.into(), 990.into()), "far"),
+            (GfaLocation::new(6.into(), 2.into()), "near"),
+            (GfaLocation::new(4.into(), 0.into()), "nearest"),
+        ],
+    )
+    .unwrap();
+    let query = GfaLocation::new(DirectedNodeIndex::new(0), 3.into());
+
+    let hits = index
+        .nearest(&graph, query, SearchDirection::Downstream, 3, None, false)
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["nearest", "near", "far"],
+    );
+    assert_eq!(
+        hits.iter()
+            .map(|hit| hit.distance().into_usize())
+            .collect::<Vec<_>>(),
+        [0, 5, 990],
+    );
+
+    let hits = index
+        .nearest(
+            &graph,
+            query,
+            SearchDirection::Downstream,
+            3,
+            Some(10.into()),
+            false,
+        )
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["nearest", "near"],
+    );
+}
+
+#[test]
+fn test_nearest_upstream_with_asymmetric_overlaps() {
+    let gfa = "S\tX\t*\tLN:i:10
+S\tY\t*\tLN:i:10
+S\tQ\t*\tLN:i:10
+L\tX\t+\tQ\t+\t6I
+L\tY\t+\tQ\t+\t3D
+";
+    let graph = read_gfa1_lengths::<u8>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    let index = AnnotationIndex::new(
+        &graph,
+        [
+            (GfaLocation::new(0.into(), 0.into()), "x"),
+            (GfaLocation::new(2.into(), 0.into()), "y"),
+        ],
+    )
+    .unwrap();
+    let query = GfaLocation::new(DirectedNodeIndex::new(4), 10.into());
+
+    let hits = index
+        .nearest(&graph, query, SearchDirection::Upstream, 2, None, true)
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["x", "y"],
+    );
+    assert_eq!(
+        hits.iter()
+            .map(|hit| hit.distance().into_usize())
+            .collect::<Vec<_>>(),
+        [14, 20],
+    );
+    assert_eq!(
+        hits[0].path().unwrap().iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 0.into(), 10.into()),
+            PathElement::new(4.into(), 6.into(), 10.into()),
+        ],
+    );
+
+    let hits = index
+        .nearest(&graph, query, SearchDirection::Upstream, 1, None, false)
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["x"],
+    );
+
+    let hits = index
+        .nearest(
+            &graph,
+            query,
+            SearchDirection::Upstream,
+            2,
+            Some(15.into()),
+            false,
+        )
+        .unwrap();
+    assert_eq!(
+        hits.iter().map(|hit| *hit.label()).collect::<Vec<_>>(),
+        ["x"],
+    );
+}
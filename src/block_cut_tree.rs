@@ -0,0 +1,463 @@
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use bidirected_adjacency_array::{
+    graph::BidirectedAdjacencyArray,
+    index::{DirectedNodeIndex, EdgeIndex, GraphIndexInteger, NodeIndex},
+};
+use binary_heap_plus::BinaryHeap;
+use optional_numeric_index::implement_generic_index;
+
+use crate::{
+    dijkstra::shortest_path,
+    graph::SequenceGraph,
+    heap_size::{hash_map_heap_size, nested_vec_heap_size, vec_heap_size},
+    location::GfaLocation,
+    path::{GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+implement_generic_index!(pub BlockIndex, pub OptionalBlockIndex);
+
+/// The block-cut tree of the underlying undirected graph of a bidirected graph.
+///
+/// Blocks are the biconnected components of the graph, and articulation nodes are the nodes that belong to more than one block.
+/// Self loops do not belong to any block, and a node without other edges forms a block on its own.
+///
+/// A distance query runs a single search over the union of the blocks on the tree path between the source and the target,
+/// and does not compose distances precomputed within the blocks.
+/// A walk may leave these blocks through an articulation node only to turn around and come back through the same node in the other orientation.
+/// The shortest such turnaround into each side of each articulation node is precomputed,
+/// which takes one search within a block per pair of adjacent block and articulation node.
+/// Overlaps must not exceed the lengths of the nodes they enter.
+pub struct BlockCutTree<IndexType, LengthType> {
+    blocks: Vec<Block<IndexType>>,
+    node_blocks: Vec<Vec<BlockIndex<IndexType>>>,
+    /// The shortest walk from the end of the articulation node into the block and back to the end of the inverse articulation node.
+    /// Missing entries mean that there is no such walk.
+    turnarounds:
+        HashMap<(DirectedNodeIndex<IndexType>, BlockIndex<IndexType>), GfaPathLength<LengthType>>,
+    /// The parent and the depth of each vertex of the tree, rooted at the first block of each connected component.
+    tree_links: HashMap<TreeVertex<IndexType>, (Option<TreeVertex<IndexType>>, usize)>,
+}
+
+struct Block<IndexType> {
+    nodes: Vec<NodeIndex<IndexType>>,
+    edges: Vec<EdgeIndex<IndexType>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TreeVertex<IndexType> {
+    Block(BlockIndex<IndexType>),
+    Articulation(NodeIndex<IndexType>),
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    BlockCutTree<IndexType, LengthType>
+{
+    /// Build the block-cut tree and precompute the turnarounds.
+    ///
+    /// Returns an error if the length of a turnaround does not fit into the length type.
+    pub fn new<NodeData, EdgeData>(
+        graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    ) -> Result<Self, LengthOverflowError>
+    where
+        BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>:
+            SequenceGraph<IndexType, LengthType = LengthType>,
+    {
+        let blocks = biconnected_components(graph);
+        let mut node_blocks = vec![Vec::new(); graph.node_count()];
+        for (block_index, block) in blocks.iter().enumerate() {
+            for node in &block.nodes {
+                node_blocks[node.into_usize()].push(BlockIndex::from_usize(block_index));
+            }
+        }
+
+        let mut tree = Self {
+            blocks,
+            node_blocks,
+            turnarounds: HashMap::new(),
+            tree_links: HashMap::new(),
+        };
+
+        // Turnarounds into a block depend on the turnarounds at the other articulation nodes of the block.
+        // So they are computed away from an arbitrary root first, and then towards it.
+        let mut preorder = Vec::new();
+        let mut tree_links = HashMap::new();
+        for root in 0..tree.blocks.len() {
+            let root = TreeVertex::Block(BlockIndex::from_usize(root));
+            if tree_links.contains_key(&root) {
+                continue;
+            }
+
+            tree_links.insert(root, (None, 0));
+            let mut stack = vec![(root, None)];
+            while let Some((vertex, parent)) = stack.pop() {
+                preorder.push((vertex, parent));
+                let depth = tree_links[&vertex].1;
+                for neighbour in tree.tree_neighbours(vertex) {
+                    if let Entry::Vacant(entry) = tree_links.entry(neighbour) {
+                        entry.insert((Some(vertex), depth + 1));
+                        stack.push((neighbour, Some(vertex)));
+                    }
+                }
+            }
+        }
+        tree.tree_links = tree_links;
+
+        for (vertex, parent) in preorder.iter().rev() {
+            if let (TreeVertex::Block(block), Some(TreeVertex::Articulation(node))) =
+                (vertex, parent)
+            {
+                tree.compute_turnarounds(graph, *node, *block)?;
+            }
+        }
+        for (vertex, parent) in &preorder {
+            if let (TreeVertex::Articulation(node), Some(TreeVertex::Block(block))) =
+                (vertex, parent)
+            {
+                tree.compute_turnarounds(graph, *node, *block)?;
+            }
+        }
+
+        Ok(tree)
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the number of precomputed turnarounds.
+    pub fn turnaround_count(&self) -> usize {
+        self.turnarounds.len()
+    }
+
+    /// Returns the approximate number of bytes that the tree allocates on the heap.
+    pub fn heap_size(&self) -> usize {
+        let block_size = self
+            .blocks
+            .iter()
+            .map(|block| vec_heap_size(&block.nodes) + vec_heap_size(&block.edges))
+            .sum::<usize>();
+        vec_heap_size(&self.blocks)
+            + block_size
+            + nested_vec_heap_size(&self.node_blocks)
+            + hash_map_heap_size(&self.turnarounds)
+            + hash_map_heap_size(&self.tree_links)
+    }
+
+    /// The nodes of the block, sorted.
+    pub fn block_nodes(&self, block: BlockIndex<IndexType>) -> &[NodeIndex<IndexType>] {
+        &self.blocks[block.into_usize()].nodes
+    }
+
+    /// The edges of the block, excluding self loops.
+    pub fn block_edges(&self, block: BlockIndex<IndexType>) -> &[EdgeIndex<IndexType>] {
+        &self.blocks[block.into_usize()].edges
+    }
+
+    /// The blocks that contain the node.
+    pub fn blocks_of_node(&self, node: NodeIndex<IndexType>) -> &[BlockIndex<IndexType>] {
+        &self.node_blocks[node.into_usize()]
+    }
+
+    /// Returns true if removing the node disconnects its connected component.
+    pub fn is_articulation(&self, node: NodeIndex<IndexType>) -> bool {
+        self.node_blocks[node.into_usize()].len() > 1
+    }
+
+    pub fn iter_articulation_nodes(&self) -> impl Iterator<Item = NodeIndex<IndexType>> {
+        (0..self.node_blocks.len())
+            .map(NodeIndex::from_usize)
+            .filter(|node| self.is_articulation(*node))
+    }
+
+    /// Iterate over the edges whose removal disconnects their connected component.
+    ///
+    /// These are the blocks that consist of a single edge.
+    pub fn iter_bridges(&self) -> impl Iterator<Item = EdgeIndex<IndexType>> {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block.edges[..] {
+                [edge] => Some(edge),
+                _ => None,
+            })
+    }
+
+    /// Returns the length of the shortest path from `source` to `target`, or `None` if there is none.
+    ///
+    /// The result is the same as the length of the path returned by [`shortest_path`].
+    /// If the shortest walk between the nodes ends before the target, which can only happen if the target lies before the source within overlaps,
+    /// the query falls back to the search over the whole graph.
+    /// Returns an error if the length of a walk does not fit into the length type.
+    pub fn distance(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<Option<GfaPathLength<LengthType>>, LengthOverflowError> {
+        let (source_node, target_node) = (
+            source.node().into_bidirected(),
+            target.node().into_bidirected(),
+        );
+        let Some(path_blocks) = self.tree_path(source_node, target_node) else {
+            return Ok(None);
+        };
+
+        let is_in_region = |node: NodeIndex<IndexType>| {
+            node == source_node
+                || node == target_node
+                || self.node_blocks[node.into_usize()]
+                    .iter()
+                    .any(|block| path_blocks.contains(block))
+        };
+        let turnaround = |node: DirectedNodeIndex<IndexType>| {
+            self.min_turnaround(node, |block| !path_blocks.contains(&block))
+        };
+        let Some(walk_length) = search(
+            graph,
+            source.node(),
+            target.node(),
+            is_in_region,
+            turnaround,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        // Costs are measured from the source to the end of the node, as in the search over the whole graph.
+        let cost = graph
+            .node_length(source.node())
+            .checked_sub(source.offset().into_length())?
+            .checked_add(walk_length)?;
+        let target_threshold = graph
+            .node_length(target.node())
+            .checked_sub(target.offset().into_length())?;
+        if cost < target_threshold {
+            Ok(shortest_path(graph, source, target)?.map(|path| path.length()))
+        } else {
+            Ok(Some(cost - target_threshold))
+        }
+    }
+
+    fn tree_neighbours(&self, vertex: TreeVertex<IndexType>) -> Vec<TreeVertex<IndexType>> {
+        match vertex {
+            TreeVertex::Block(block) => self.blocks[block.into_usize()]
+                .nodes
+                .iter()
+                .filter(|node| self.is_articulation(**node))
+                .map(|node| TreeVertex::Articulation(*node))
+                .collect(),
+            TreeVertex::Articulation(node) => self.node_blocks[node.into_usize()]
+                .iter()
+                .map(|block| TreeVertex::Block(*block))
+                .collect(),
+        }
+    }
+
+    fn tree_vertex(&self, node: NodeIndex<IndexType>) -> TreeVertex<IndexType> {
+        if self.is_articulation(node) {
+            TreeVertex::Articulation(node)
+        } else {
+            TreeVertex::Block(self.node_blocks[node.into_usize()][0])
+        }
+    }
+
+    /// Returns the blocks on the path between the nodes in the block-cut tree, or `None` if they are not connected.
+    ///
+    /// The path is found by walking up from the deeper end until both ends meet.
+    fn tree_path(
+        &self,
+        source: NodeIndex<IndexType>,
+        target: NodeIndex<IndexType>,
+    ) -> Option<HashSet<BlockIndex<IndexType>>> {
+        let (mut source, mut target) = (self.tree_vertex(source), self.tree_vertex(target));
+        let mut blocks = HashSet::new();
+        let mut insert = |vertex| {
+            if let TreeVertex::Block(block) = vertex {
+                blocks.insert(block);
+            }
+        };
+        insert(source);
+        insert(target);
+
+        while source != target {
+            let (source_parent, source_depth) = self.tree_links[&source];
+            let (target_parent, target_depth) = self.tree_links[&target];
+            // Roots of different connected components have no parents.
+            if source_depth >= target_depth {
+                source = source_parent?;
+                insert(source);
+            } else {
+                target = target_parent?;
+                insert(target);
+            }
+        }
+        Some(blocks)
+    }
+
+    /// The shortest turnaround at the directed node into any of the accepted blocks.
+    fn min_turnaround(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+        is_accepted: impl Fn(BlockIndex<IndexType>) -> bool,
+    ) -> Option<GfaPathLength<LengthType>> {
+        if !self.is_articulation(node.into_bidirected()) {
+            return None;
+        }
+
+        self.node_blocks[node.into_bidirected().into_usize()]
+            .iter()
+            .filter(|block| is_accepted(**block))
+            .filter_map(|block| self.turnarounds.get(&(node, *block)).copied())
+            .min()
+    }
+
+    fn compute_turnarounds(
+        &mut self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        node: NodeIndex<IndexType>,
+        block: BlockIndex<IndexType>,
+    ) -> Result<(), LengthOverflowError> {
+        for forward in [true, false] {
+            let start = DirectedNodeIndex::from_bidirected(node, forward);
+            let is_in_block =
+                |node: NodeIndex<IndexType>| self.node_blocks[node.into_usize()].contains(&block);
+            // Turnarounds at other articulation nodes lead away from the block.
+            let turnaround = |other: DirectedNodeIndex<IndexType>| {
+                if other.into_bidirected() == node {
+                    None
+                } else {
+                    self.min_turnaround(other, |other_block| other_block != block)
+                }
+            };
+            if let Some(length) = search(graph, start, start.invert(), is_in_block, turnaround)? {
+                self.turnarounds.insert((start, block), length);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Search the shortest walk from the end of `source` to the end of `target` that stays within the region,
+/// where each directed node may also be left by turning around at the given cost.
+fn search<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: DirectedNodeIndex<IndexType>,
+    target: DirectedNodeIndex<IndexType>,
+    is_in_region: impl Fn(NodeIndex<IndexType>) -> bool,
+    turnaround: impl Fn(DirectedNodeIndex<IndexType>) -> Option<GfaPathLength<LengthType>>,
+) -> Result<Option<GfaPathLength<LengthType>>, LengthOverflowError> {
+    let mut open_list = BinaryHeap::new_min();
+    let mut closed_list = HashSet::new();
+    open_list.push((GfaPathLength::from_usize(0), source));
+
+    while let Some((cost, node)) = open_list.pop() {
+        if node == target {
+            return Ok(Some(cost));
+        }
+        if !closed_list.insert(node) {
+            continue;
+        }
+
+        for successor in graph.iter_successors(node) {
+            if is_in_region(successor.node().into_bidirected())
+                && !closed_list.contains(&successor.node())
+            {
+                open_list.push((successor.extend_cost(cost, graph)?, successor.node()));
+            }
+        }
+        if let Some(turnaround) = turnaround(node) {
+            open_list.push((cost.checked_add(turnaround)?, graph.invert(node)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Compute the biconnected components of the underlying undirected graph with the algorithm of Hopcroft and Tarjan.
+fn biconnected_components<IndexType: GraphIndexInteger, NodeData, EdgeData>(
+    graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+) -> Vec<Block<IndexType>> {
+    let node_count = graph.node_count();
+    let mut adjacency = vec![Vec::new(); node_count];
+    for edge_index in graph.iter_edges() {
+        let edge = graph.edge(edge_index);
+        let (from, to) = (edge.from().into_bidirected(), edge.to().into_bidirected());
+        if from != to {
+            adjacency[from.into_usize()].push((to, edge_index));
+            adjacency[to.into_usize()].push((from, edge_index));
+        }
+    }
+
+    let mut blocks = Vec::new();
+    let mut discovery = vec![usize::MAX; node_count];
+    let mut low = vec![usize::MAX; node_count];
+    let mut time = 0;
+    let mut edge_stack = Vec::new();
+
+    for root in 0..node_count {
+        if discovery[root] != usize::MAX {
+            continue;
+        }
+        discovery[root] = time;
+        low[root] = time;
+        time += 1;
+        if adjacency[root].is_empty() {
+            blocks.push(Block {
+                nodes: vec![NodeIndex::from_usize(root)],
+                edges: Vec::new(),
+            });
+            continue;
+        }
+
+        // Each frame holds a node, the edge it was entered through, and the position of the next neighbour to visit.
+        let mut stack = vec![(root, None, 0)];
+        while let Some((node, parent_edge, position)) = stack.last_mut() {
+            let node = *node;
+            if let Some(&(neighbour, edge)) = adjacency[node].get(*position) {
+                *position += 1;
+                if Some(edge) == *parent_edge {
+                    continue;
+                }
+
+                let neighbour = neighbour.into_usize();
+                if discovery[neighbour] == usize::MAX {
+                    edge_stack.push((node, neighbour, edge));
+                    discovery[neighbour] = time;
+                    low[neighbour] = time;
+                    time += 1;
+                    stack.push((neighbour, Some(edge), 0));
+                } else if discovery[neighbour] < discovery[node] {
+                    edge_stack.push((node, neighbour, edge));
+                    low[node] = low[node].min(discovery[neighbour]);
+                }
+            } else {
+                stack.pop();
+                let Some(&(parent, _, _)) = stack.last() else {
+                    continue;
+                };
+                low[parent] = low[parent].min(low[node]);
+
+                if low[node] >= discovery[parent] {
+                    let mut nodes = Vec::new();
+                    let mut edges = Vec::new();
+                    while let Some((from, to, edge)) = edge_stack.pop() {
+                        nodes.extend([NodeIndex::from_usize(from), NodeIndex::from_usize(to)]);
+                        edges.push(edge);
+                        if (from, to) == (parent, node) {
+                            break;
+                        }
+                    }
+                    nodes.sort_unstable();
+                    nodes.dedup();
+                    edges.sort_unstable();
+                    blocks.push(Block { nodes, edges });
+                }
+            }
+        }
+    }
+
+    blocks
+}
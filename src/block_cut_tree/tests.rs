@@ -0,0 +1,97 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, NodeIndex},
+    io::gfa1::PlainGfaNodeData,
+};
+
+use crate::{
+    block_cut_tree::{BlockCutTree, BlockIndex},
+    dijkstra::shortest_path,
+    location::GfaLocation,
+    test_util::{assert_same_distances, random_graph},
+};
+
+#[test]
+fn test_turnaround_behind_articulation() {
+    // A and B are joined by a bridge, as are B and C, and C has a hairpin.
+    // Going from A back into A in reverse requires turning around at C, outside of the block of A.
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BB"),
+        PlainGfaNodeData::new("C", "CCCC"),
+    ];
+    let edges = [(0, 2, 1), (2, 4, 0), (4, 5, 2)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u16, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let tree = BlockCutTree::new(&graph).unwrap();
+    assert_eq!(tree.block_count(), 2);
+    assert_eq!(
+        tree.iter_articulation_nodes().collect::<Vec<_>>(),
+        [NodeIndex::from_usize(1)],
+    );
+    assert_eq!(tree.iter_bridges().count(), 2);
+    assert_eq!(tree.blocks_of_node(NodeIndex::from_usize(1)).len(), 2,);
+    assert_eq!(
+        tree.block_nodes(tree.blocks_of_node(NodeIndex::from_usize(0))[0]),
+        [NodeIndex::from_usize(0), NodeIndex::from_usize(1)],
+    );
+
+    let source = GfaLocation::new(0.into(), 0.into());
+    let target = GfaLocation::new(1.into(), 3.into());
+    let distance = tree.distance(&graph, source, target).unwrap().unwrap();
+    assert_eq!(
+        Some(distance),
+        shortest_path(&graph, source, target)
+            .unwrap()
+            .map(|path| path.length()),
+    );
+    assert_same_distances(&graph, |source, target| {
+        tree.distance(&graph, source, target).unwrap()
+    });
+}
+
+#[test]
+fn test_cycle_is_one_block() {
+    let nodes = ["A", "B", "C"]
+        .map(|name| PlainGfaNodeData::new(name, "AC"))
+        .to_vec();
+    let edges = [(0, 2), (2, 4), (4, 0)].map(|(from, to)| {
+        BidirectedEdge::new_gfa(DirectedNodeIndex::new(from), DirectedNodeIndex::new(to), 0)
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u16, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let tree = BlockCutTree::new(&graph).unwrap();
+    assert_eq!(tree.block_count(), 1);
+    assert_eq!(tree.block_edges(BlockIndex::from_usize(0)).len(), 3);
+    assert_eq!(tree.iter_articulation_nodes().count(), 0);
+    assert_eq!(tree.iter_bridges().count(), 0);
+}
+
+#[test]
+fn test_random_graphs() {
+    // Sparse graphs have many blocks and articulation nodes.
+    let mut articulation_count = 0;
+    for seed in 0..4 {
+        let graph = random_graph(seed, 16, 18);
+        let tree = BlockCutTree::new(&graph).unwrap();
+        articulation_count += tree.iter_articulation_nodes().count();
+        assert_same_distances(&graph, |source, target| {
+            tree.distance(&graph, source, target).unwrap()
+        });
+    }
+    assert!(articulation_count > 0);
+
+    let graph = random_graph(4, 16, 32);
+    let tree = BlockCutTree::new(&graph).unwrap();
+    assert_same_distances(&graph, |source, target| {
+        tree.distance(&graph, source, target).unwrap()
+    });
+}
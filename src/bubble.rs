@@ -0,0 +1,517 @@
+//! Detection of superbubbles in bidirected graphs.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::Write,
+};
+
+use bidirected_adjacency_array::{
+    graph::BidirectedAdjacencyArray,
+    index::{DirectedNodeIndex, GraphIndexInteger},
+};
+
+use crate::{
+    components::strongly_connected_components,
+    gfa_graph_extensions::GfaNodeNameExt,
+    graph::{ClampedOverlaps, SequenceGraph},
+    path::{GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A superbubble, i.e. an acyclic subgraph that is entered only through its entrance and left only through its exit.
+///
+/// Each superbubble is found in both orientations of the graph.
+/// Only the orientation with the smaller entrance and exit is reported.
+/// Single edges between an entrance and an exit are not reported as superbubbles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Superbubble<IndexType, LengthType> {
+    entrance: DirectedNodeIndex<IndexType>,
+    exit: DirectedNodeIndex<IndexType>,
+    interior: Vec<DirectedNodeIndex<IndexType>>,
+    parent: Option<usize>,
+    min_length: GfaPathLength<LengthType>,
+    max_length: GfaPathLength<LengthType>,
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    Superbubble<IndexType, LengthType>
+{
+    pub fn entrance(&self) -> DirectedNodeIndex<IndexType> {
+        self.entrance
+    }
+
+    pub fn exit(&self) -> DirectedNodeIndex<IndexType> {
+        self.exit
+    }
+
+    /// The nodes strictly between the entrance and the exit, sorted.
+    pub fn interior(&self) -> &[DirectedNodeIndex<IndexType>] {
+        &self.interior
+    }
+
+    /// The index of the smallest superbubble that contains this superbubble, if any.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    /// The length of the shortest walk from the end of the entrance to the end of the exit.
+    pub fn min_length(&self) -> GfaPathLength<LengthType> {
+        self.min_length
+    }
+
+    /// The length of the longest walk from the end of the entrance to the end of the exit.
+    pub fn max_length(&self) -> GfaPathLength<LengthType> {
+        self.max_length
+    }
+}
+
+/// An interval of the unrolled graph that may be a superbubble, see `superbubble_candidates`.
+struct SuperbubbleCandidate<IndexType> {
+    entrance: DirectedNodeIndex<IndexType>,
+    exit: DirectedNodeIndex<IndexType>,
+    /// The position of the entrance in the topological order of the unrolled graph.
+    start: usize,
+    /// The position of the exit in the topological order of the unrolled graph.
+    end: usize,
+}
+
+/// Find all superbubbles of the graph, sorted by entrance and exit.
+///
+/// Candidate entrances are found in linear time as intervals of a topological order of an acyclic unrolling of the graph.
+/// The smallest superbubble of each candidate entrance is then found with the algorithm of Onodera et al. (2013),
+/// which stops once it visits more nodes than the candidate contains.
+/// The nesting is derived from the candidate intervals, which are nested or disjoint.
+/// So the running time is linear in the size of the graph plus the size of the candidates, up to sorting the candidates.
+/// The search works on the graph directly and does not use its block or SPQR decomposition.
+/// Overlaps are clamped to the lengths of the nodes they consume when computing walk lengths.
+/// Returns an error if the length of a walk through a superbubble does not fit into the length type.
+pub fn find_superbubbles<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+) -> Result<Vec<Superbubble<IndexType, LengthType>>, LengthOverflowError> {
+    let graph = ClampedOverlaps::new(graph);
+    let candidates = superbubble_candidates(&graph);
+    let mut max_sizes = BTreeMap::new();
+    for candidate in &candidates {
+        let size = candidate.end - candidate.start + 1;
+        let max_size = max_sizes.entry(candidate.entrance).or_insert(size);
+        *max_size = size.max(*max_size);
+    }
+
+    let mut found = Vec::new();
+    for (entrance, max_size) in max_sizes {
+        if let Some(superbubble) = find_superbubble(&graph, entrance, max_size)?
+            && !superbubble.interior.is_empty()
+        {
+            found.push(superbubble);
+        }
+    }
+
+    Ok(finish_superbubbles(&graph, found, &candidates))
+}
+
+/// Compute the nesting of the superbubbles, keep one orientation of each superbubble and sort them.
+///
+/// A superbubble is nested in the smallest other superbubble whose candidate interval contains one of its candidate intervals.
+fn finish_superbubbles<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    found: Vec<Superbubble<IndexType, LengthType>>,
+    candidates: &[SuperbubbleCandidate<IndexType>],
+) -> Vec<Superbubble<IndexType, LengthType>> {
+    let indices = found
+        .iter()
+        .enumerate()
+        .map(|(index, superbubble)| ((superbubble.entrance, superbubble.exit), index))
+        .collect::<HashMap<_, _>>();
+
+    // The candidate intervals are nested or disjoint, so sweeping them by their start
+    // keeps the intervals that contain the current one on a stack, with the smallest one on top.
+    let mut intervals = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let index = *indices.get(&(candidate.entrance, candidate.exit))?;
+            Some((candidate.start, Reverse(candidate.end), index))
+        })
+        .collect::<Vec<_>>();
+    intervals.sort_unstable();
+    let mut parents = vec![None; found.len()];
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for (_, Reverse(end), index) in intervals {
+        while stack.last().is_some_and(|&(stack_end, _)| stack_end < end) {
+            stack.pop();
+        }
+        if let Some(&(_, parent)) = stack.last()
+            && parent != index
+            && parents[index].is_none_or(|other: usize| {
+                found[parent].interior.len() < found[other].interior.len()
+            })
+        {
+            parents[index] = Some(parent);
+        }
+        stack.push((end, index));
+    }
+
+    // Keep only one orientation of each superbubble.
+    let kept_ends = |superbubble: &Superbubble<IndexType, LengthType>| {
+        let inverse = (
+            graph.invert(superbubble.exit),
+            graph.invert(superbubble.entrance),
+        );
+        if indices.contains_key(&inverse) {
+            (superbubble.entrance, superbubble.exit).min(inverse)
+        } else {
+            (superbubble.entrance, superbubble.exit)
+        }
+    };
+    let mut kept = found
+        .iter()
+        .map(kept_ends)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .enumerate()
+        .map(|(index, ends)| (ends, index))
+        .collect::<BTreeMap<_, _>>();
+    let mut kept_parents = vec![None; kept.len()];
+    for (index, superbubble) in found.iter().enumerate() {
+        let Some(parent) = parents[index] else {
+            continue;
+        };
+        let kept_parent = &mut kept_parents[kept[&kept_ends(superbubble)]];
+        if kept_parent
+            .is_none_or(|other: usize| found[parent].interior.len() < found[other].interior.len())
+        {
+            *kept_parent = Some(parent);
+        }
+    }
+    let kept_parents = kept_parents
+        .into_iter()
+        .map(|parent| parent.map(|parent| kept[&kept_ends(&found[parent])]))
+        .collect::<Vec<_>>();
+
+    let mut superbubbles = found
+        .into_iter()
+        .filter(|superbubble| {
+            kept.remove(&(superbubble.entrance, superbubble.exit))
+                .is_some()
+        })
+        .collect::<Vec<_>>();
+    superbubbles.sort_by_key(|superbubble| (superbubble.entrance, superbubble.exit));
+    for (superbubble, parent) in superbubbles.iter_mut().zip(kept_parents) {
+        superbubble.parent = parent;
+    }
+
+    superbubbles
+}
+
+/// Write the superbubbles as tab-separated values.
+///
+/// Each line contains the entrance, the exit, the index of the parent or `.` if there is none, and the minimum and maximum lengths.
+/// Nodes are written as their segment names followed by their orientation.
+pub fn write_superbubbles<IndexType: GraphIndexInteger, NodeData: GfaNodeNameExt, EdgeData>(
+    superbubbles: &[Superbubble<IndexType, impl GraphIndexInteger>],
+    graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    let name = |node: DirectedNodeIndex<IndexType>| {
+        let sign = if node.is_forward() { '+' } else { '-' };
+        format!(
+            "{}{sign}",
+            graph.node_data(node.into_bidirected()).segment_name()
+        )
+    };
+
+    writeln!(writer, "#entrance\texit\tparent\tmin_length\tmax_length")?;
+    for superbubble in superbubbles {
+        let parent = superbubble
+            .parent
+            .map(|parent| parent.to_string())
+            .unwrap_or_else(|| ".".to_string());
+        writeln!(
+            writer,
+            "{}\t{}\t{parent}\t{}\t{}",
+            name(superbubble.entrance),
+            name(superbubble.exit),
+            superbubble.min_length,
+            superbubble.max_length,
+        )?;
+    }
+    Ok(())
+}
+
+/// Find the candidate superbubbles as intervals of a topological order of an acyclic unrolling of the graph.
+///
+/// Superbubbles are either acyclic parts of the graph between strongly connected components,
+/// or lie entirely within a strongly connected component.
+/// Each strongly connected component is unrolled into two copies of its nodes:
+/// the edges within the component that close a cycle in a depth-first search lead from the first copy into the second one,
+/// and all other edges within the component are present in both copies.
+/// Edges between components leave from the second copy and enter the first copy.
+///
+/// In a depth-first topological order of the resulting acyclic graph, each superbubble is an interval from its entrance to its exit.
+/// An interval is a superbubble if the children of all its nodes but the exit lie within the interval,
+/// and the parents of all its nodes but the entrance lie within the interval.
+/// The smallest interval that is closed under children is found for all entrances at once from right to left,
+/// since these intervals are nested or disjoint.
+fn superbubble_candidates<IndexType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType>,
+) -> Vec<SuperbubbleCandidate<IndexType>> {
+    let node_count = 2 * graph.node_count();
+    let (strong_components, strong_component_count) = strongly_connected_components(graph);
+    let successors = (0..node_count)
+        .map(|node| {
+            graph
+                .iter_successors(DirectedNodeIndex::from_usize(node))
+                .map(|successor| successor.node().into_usize())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let component = |node: usize| strong_components[node].into_usize();
+
+    // A component is cyclic if it has more than one node or a self loop.
+    let mut component_sizes = vec![0; strong_component_count];
+    for node in 0..node_count {
+        component_sizes[component(node)] += 1;
+    }
+    let is_cyclic = (0..node_count)
+        .map(|node| component_sizes[component(node)] > 1 || successors[node].contains(&node))
+        .collect::<Vec<_>>();
+
+    // The unrolled graph has the first copy of each node at its index, and the second copy of each node of a cyclic component behind all nodes.
+    // The depth-first search of each cyclic component starts at a node that is entered from outside of the component if there is one,
+    // since such a node cannot lie inside a superbubble.
+    let mut unrolled = vec![Vec::new(); 2 * node_count];
+    let mut roots = vec![None; strong_component_count];
+    for node in 0..node_count {
+        for &successor in &successors[node] {
+            if component(node) != component(successor) {
+                let from = if is_cyclic[node] {
+                    node_count + node
+                } else {
+                    node
+                };
+                unrolled[from].push(successor);
+                if is_cyclic[successor] {
+                    roots[component(successor)] = Some(successor);
+                }
+            }
+        }
+    }
+    let mut is_visited = vec![false; node_count];
+    let mut is_on_stack = vec![false; node_count];
+    for node in 0..node_count {
+        if !is_cyclic[node] || is_visited[node] {
+            continue;
+        }
+        let root = roots[component(node)].unwrap_or(node);
+        is_visited[root] = true;
+        is_on_stack[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((node, next_successor)) = stack.last_mut() {
+            let node = *node;
+            let Some(&successor) = successors[node].get(*next_successor) else {
+                is_on_stack[node] = false;
+                stack.pop();
+                continue;
+            };
+            *next_successor += 1;
+            if component(successor) != component(node) {
+                continue;
+            }
+
+            if is_on_stack[successor] {
+                unrolled[node].push(node_count + successor);
+            } else {
+                unrolled[node].push(successor);
+                unrolled[node_count + node].push(node_count + successor);
+                if !is_visited[successor] {
+                    is_visited[successor] = true;
+                    is_on_stack[successor] = true;
+                    stack.push((successor, 0));
+                }
+            }
+        }
+    }
+
+    // Order the unrolled graph topologically by reverse postorder of a depth-first search from its sources.
+    let vertices = (0..2 * node_count)
+        .filter(|vertex| *vertex < node_count || is_cyclic[vertex - node_count])
+        .collect::<Vec<_>>();
+    let mut has_parent = vec![false; 2 * node_count];
+    for children in &unrolled {
+        for child in children {
+            has_parent[*child] = true;
+        }
+    }
+    let mut postorder = Vec::with_capacity(vertices.len());
+    let mut is_visited = vec![false; 2 * node_count];
+    for root in vertices
+        .iter()
+        .copied()
+        .filter(|vertex| !has_parent[*vertex])
+    {
+        is_visited[root] = true;
+        let mut stack = vec![(root, 0)];
+        while let Some((vertex, next_child)) = stack.last_mut() {
+            let vertex = *vertex;
+            if let Some(&child) = unrolled[vertex].get(*next_child) {
+                *next_child += 1;
+                if !is_visited[child] {
+                    is_visited[child] = true;
+                    stack.push((child, 0));
+                }
+            } else {
+                postorder.push(vertex);
+                stack.pop();
+            }
+        }
+    }
+    let order = postorder.into_iter().rev().collect::<Vec<_>>();
+    let mut position = vec![usize::MAX; 2 * node_count];
+    for (index, vertex) in order.iter().enumerate() {
+        position[*vertex] = index;
+    }
+
+    // For each position, the last position of a child, and one more than the first position of a parent, or zero for sources.
+    let mut last_child = vec![None; order.len()];
+    let mut first_parent = vec![0; order.len()];
+    for (index, vertex) in order.iter().enumerate() {
+        for child in &unrolled[*vertex] {
+            let child = position[*child];
+            last_child[index] = last_child[index].max(Some(child));
+            if first_parent[child] == 0 || first_parent[child] > index + 1 {
+                first_parent[child] = index + 1;
+            }
+        }
+    }
+
+    // The interval of a position ends at the first position at which the interval is closed under children.
+    // Its parent bound is the minimum of `first_parent` over the interval without its end.
+    let mut interval_ends = vec![None; order.len()];
+    let mut parent_bounds = vec![0; order.len()];
+    let mut candidates = Vec::new();
+    for start in (0..order.len()).rev() {
+        let Some(mut end) = last_child[start] else {
+            continue;
+        };
+        let mut parent_bound = usize::MAX;
+        let mut next = start + 1;
+        while next < end {
+            let Some(next_end) = interval_ends[next] else {
+                break;
+            };
+            parent_bound = parent_bound.min(parent_bounds[next]);
+            end = end.max(next_end);
+            next = next_end;
+        }
+        if next < end {
+            continue;
+        }
+
+        interval_ends[start] = Some(end);
+        parent_bounds[start] = parent_bound.min(first_parent[start]);
+        if parent_bound.min(first_parent[end]) > start {
+            candidates.push(SuperbubbleCandidate {
+                entrance: DirectedNodeIndex::from_usize(order[start] % node_count),
+                exit: DirectedNodeIndex::from_usize(order[end] % node_count),
+                start,
+                end,
+            });
+        }
+    }
+
+    candidates
+}
+
+/// Find the smallest superbubble with the given entrance that has at most `max_size` nodes, including its entrance and exit.
+fn find_superbubble<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    entrance: DirectedNodeIndex<IndexType>,
+    max_size: usize,
+) -> Result<Option<Superbubble<IndexType, LengthType>>, LengthOverflowError> {
+    let predecessors = |node: DirectedNodeIndex<IndexType>| {
+        graph
+            .iter_successors(graph.invert(node))
+            .map(|successor| graph.invert(successor.node()))
+    };
+
+    // Nodes are visited once all their predecessors are visited, so the visiting order is topological.
+    let mut stack = vec![entrance];
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    let mut seen = BTreeSet::new();
+    let exit = loop {
+        let Some(node) = stack.pop() else {
+            return Ok(None);
+        };
+        visited.insert(node);
+        order.push(node);
+        seen.remove(&node);
+        // The exit is not part of the order yet.
+        if order.len() >= max_size {
+            return Ok(None);
+        }
+
+        let mut has_successors = false;
+        for successor in graph.iter_successors(node) {
+            has_successors = true;
+            let successor = successor.node();
+            if successor == entrance {
+                return Ok(None);
+            }
+            seen.insert(successor);
+            if predecessors(successor).all(|predecessor| visited.contains(&predecessor))
+                && !stack.contains(&successor)
+            {
+                stack.push(successor);
+            }
+        }
+        if !has_successors {
+            return Ok(None);
+        }
+
+        if let [exit] = stack[..]
+            && seen.len() == 1
+            && seen.contains(&exit)
+        {
+            if graph
+                .iter_successors(exit)
+                .any(|successor| successor.node() == entrance)
+            {
+                return Ok(None);
+            }
+            break exit;
+        }
+    };
+
+    // Compute the walk lengths in topological order.
+    let zero = GfaPathLength::from_usize(0);
+    let mut lengths = HashMap::from([(entrance, (zero, zero))]);
+    for node in &order {
+        let (min_length, max_length) = lengths[node];
+        for successor in graph.iter_successors(*node) {
+            let (min_cost, max_cost) = (
+                successor.extend_cost(min_length, graph)?,
+                successor.extend_cost(max_length, graph)?,
+            );
+            let entry = lengths
+                .entry(successor.node())
+                .or_insert((min_cost, max_cost));
+            entry.0 = entry.0.min(min_cost);
+            entry.1 = entry.1.max(max_cost);
+        }
+    }
+    let (min_length, max_length) = lengths[&exit];
+
+    let mut interior = order[1..].to_vec();
+    interior.sort_unstable();
+    Ok(Some(Superbubble {
+        entrance,
+        exit,
+        interior,
+        parent: None,
+        min_length,
+        max_length,
+    }))
+}
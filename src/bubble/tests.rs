@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    bubble::{Superbubble, find_superbubble, find_superbubbles, write_superbubbles},
+    graph::{ClampedOverlaps, SequenceGraph},
+    test_util::random_graph,
+};
+
+fn graph(
+    nodes: &[(&str, usize)],
+    edges: &[(u8, u8)],
+) -> BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> {
+    let nodes = nodes
+        .iter()
+        .map(|(name, length)| PlainGfaNodeData::new(name, "A".repeat(*length)))
+        .collect::<Vec<_>>();
+    let edges = edges.iter().map(|&(from, to)| {
+        BidirectedEdge::new_gfa(DirectedNodeIndex::new(from), DirectedNodeIndex::new(to), 0)
+    });
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+#[test]
+fn test_simple_bubble() {
+    // A and B are the alleles between S and T, where B is entered in reverse.
+    let graph = graph(
+        &[("S", 2), ("A", 3), ("B", 5), ("T", 2)],
+        &[(0, 2), (0, 5), (2, 6), (5, 6)],
+    );
+    let superbubbles = find_superbubbles(&graph).unwrap();
+    assert_eq!(superbubbles.len(), 1);
+    let superbubble = &superbubbles[0];
+    assert_eq!(superbubble.entrance(), 0.into());
+    assert_eq!(superbubble.exit(), 6.into());
+    assert_eq!(superbubble.interior(), [2.into(), 5.into()]);
+    assert_eq!(superbubble.parent(), None);
+    assert_eq!(superbubble.min_length().into_usize(), 5);
+    assert_eq!(superbubble.max_length().into_usize(), 7);
+
+    let mut buffer = Vec::new();
+    write_superbubbles(&superbubbles, &graph, &mut buffer).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "#entrance\texit\tparent\tmin_length\tmax_length\nS+\tT+\t.\t5\t7\n",
+    );
+}
+
+#[test]
+fn test_nested_bubbles() {
+    // The bubble from X to Y is one allele of the bubble from S to T.
+    let graph = graph(
+        &[
+            ("S", 1),
+            ("X", 1),
+            ("A", 1),
+            ("B", 4),
+            ("Y", 1),
+            ("C", 1),
+            ("T", 1),
+        ],
+        &[
+            (0, 2),
+            (2, 4),
+            (2, 6),
+            (4, 8),
+            (6, 8),
+            (8, 12),
+            (0, 10),
+            (10, 12),
+        ],
+    );
+    let superbubbles = find_superbubbles(&graph).unwrap();
+    assert_eq!(
+        superbubbles
+            .iter()
+            .map(|superbubble| (
+                superbubble.entrance(),
+                superbubble.exit(),
+                superbubble.parent(),
+            ))
+            .collect::<Vec<_>>(),
+        [(0.into(), 12.into(), None), (2.into(), 8.into(), Some(0))],
+    );
+    assert_eq!(superbubbles[0].min_length().into_usize(), 2);
+    assert_eq!(superbubbles[0].max_length().into_usize(), 7);
+}
+
+#[test]
+fn test_no_bubbles() {
+    // A cycle through S and T, and a tip at A.
+    let graph = graph(
+        &[("S", 1), ("A", 1), ("B", 1), ("T", 1)],
+        &[(0, 4), (0, 6), (4, 6), (6, 0), (0, 2)],
+    );
+    assert!(find_superbubbles(&graph).unwrap().is_empty());
+}
+
+/// Keep one orientation of each superbubble, sort them and nest each superbubble in the smallest other superbubble
+/// that contains its entrance and exit in either orientation.
+fn reference_superbubbles(
+    graph: &impl SequenceGraph<u16, LengthType = u64>,
+    found: Vec<Superbubble<u16, u64>>,
+) -> Vec<Superbubble<u16, u64>> {
+    let ends = found
+        .iter()
+        .map(|superbubble| (superbubble.entrance(), superbubble.exit()))
+        .collect::<HashSet<_>>();
+    let mut superbubbles = found
+        .into_iter()
+        .filter(|superbubble| {
+            let inverse = (
+                graph.invert(superbubble.exit()),
+                graph.invert(superbubble.entrance()),
+            );
+            (superbubble.entrance(), superbubble.exit()) <= inverse || !ends.contains(&inverse)
+        })
+        .collect::<Vec<_>>();
+    superbubbles.sort_by_key(|superbubble| (superbubble.entrance(), superbubble.exit()));
+
+    let nodes = superbubbles
+        .iter()
+        .map(|superbubble| {
+            superbubble
+                .interior()
+                .iter()
+                .chain([&superbubble.entrance(), &superbubble.exit()])
+                .map(|node| node.into_bidirected())
+                .collect::<HashSet<_>>()
+        })
+        .collect::<Vec<_>>();
+    for index in 0..superbubbles.len() {
+        let (entrance, exit) = (
+            superbubbles[index].entrance().into_bidirected(),
+            superbubbles[index].exit().into_bidirected(),
+        );
+        superbubbles[index].parent = nodes
+            .iter()
+            .enumerate()
+            .filter(|(parent, parent_nodes)| {
+                *parent != index
+                    && parent_nodes.len() > nodes[index].len()
+                    && parent_nodes.contains(&entrance)
+                    && parent_nodes.contains(&exit)
+            })
+            .min_by_key(|(_, parent_nodes)| parent_nodes.len())
+            .map(|(parent, _)| parent);
+    }
+    superbubbles
+}
+
+#[test]
+fn test_random_graphs() {
+    // Compare against the search from every directed node without a size limit.
+    for seed in 0..200 {
+        let node_count = 4 + seed as usize % 12;
+        let edge_count = node_count + seed as usize % 7;
+        let graph = random_graph(seed, node_count, edge_count);
+        let clamped = ClampedOverlaps::new(&graph);
+        let found = (0..2 * graph.node_count())
+            .filter_map(|entrance| {
+                find_superbubble(
+                    &clamped,
+                    DirectedNodeIndex::from_usize(entrance),
+                    usize::MAX,
+                )
+                .unwrap()
+            })
+            .filter(|superbubble| !superbubble.interior().is_empty())
+            .collect();
+
+        assert_eq!(
+            find_superbubbles(&graph).unwrap(),
+            reference_superbubbles(&clamped, found),
+            "Superbubbles differ for seed {seed}",
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex};
+use optional_numeric_index::implement_generic_index;
+
+use crate::{
+    dijkstra,
+    graph::SequenceGraph,
+    heap_size::vec_heap_size,
+    location::GfaLocation,
+    path::{GfaPath, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+implement_generic_index!(pub ComponentIndex, pub OptionalComponentIndex);
+implement_generic_index!(pub StrongComponentIndex, pub OptionalStrongComponentIndex);
+
+/// The connected components of a graph, and the strongly connected components of its directed nodes.
+///
+/// Locations in different connected components can never reach each other,
+/// and locations in the same strongly connected component can always reach each other.
+/// Components are numbered in order of their smallest node.
+pub struct ComponentLabels<IndexType> {
+    components: Vec<ComponentIndex<IndexType>>,
+    component_count: usize,
+    strong_components: Vec<StrongComponentIndex<IndexType>>,
+    strong_component_count: usize,
+}
+
+impl<IndexType: GraphIndexInteger> ComponentLabels<IndexType> {
+    pub fn new(graph: &impl SequenceGraph<IndexType>) -> Self {
+        let (components, component_count) = connected_components(graph);
+        let (strong_components, strong_component_count) = strongly_connected_components(graph);
+        Self {
+            components,
+            component_count,
+            strong_components,
+            strong_component_count,
+        }
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.component_count
+    }
+
+    pub fn strong_component_count(&self) -> usize {
+        self.strong_component_count
+    }
+
+    /// Returns the approximate number of bytes that the labels allocate on the heap.
+    pub fn heap_size(&self) -> usize {
+        vec_heap_size(&self.components) + vec_heap_size(&self.strong_components)
+    }
+
+    /// The connected component of the node, ignoring the directions of the edges.
+    pub fn component(&self, node: NodeIndex<IndexType>) -> ComponentIndex<IndexType> {
+        self.components[node.into_usize()]
+    }
+
+    /// The strongly connected component of the directed node.
+    ///
+    /// The two orientations of a node are in the same strongly connected component if and only if a walk can turn around from one into the other.
+    pub fn strong_component(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> StrongComponentIndex<IndexType> {
+        self.strong_components[node.into_usize()]
+    }
+
+    /// Returns false if no walk leads from the source node to the target node.
+    ///
+    /// Returns true if the nodes are in the same connected component, even if the target cannot be reached.
+    pub fn may_reach(
+        &self,
+        source: DirectedNodeIndex<IndexType>,
+        target: DirectedNodeIndex<IndexType>,
+    ) -> bool {
+        self.component(source.into_bidirected()) == self.component(target.into_bidirected())
+    }
+
+    /// Find the shortest path between two locations like [`dijkstra::shortest_path`],
+    /// but return `None` without searching if they are in different connected components.
+    pub fn shortest_path<LengthType: GraphIndexInteger>(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<Option<GfaPath<IndexType, LengthType>>, LengthOverflowError> {
+        if !self.may_reach(source.node(), target.node()) {
+            return Ok(None);
+        }
+        dijkstra::shortest_path(graph, source, target)
+    }
+}
+
+/// Label the bidirected nodes with their connected components.
+fn connected_components<IndexType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType>,
+) -> (Vec<ComponentIndex<IndexType>>, usize) {
+    let mut components = vec![usize::MAX; graph.node_count()];
+    let mut component_count = 0;
+
+    for root in 0..graph.node_count() {
+        if components[root] != usize::MAX {
+            continue;
+        }
+
+        let component = component_count;
+        component_count += 1;
+        components[root] = component;
+        let mut stack = vec![NodeIndex::<IndexType>::from_usize(root)];
+        while let Some(node) = stack.pop() {
+            // The successors of both orientations cover all edges incident to the node.
+            for forward in [true, false] {
+                for successor in
+                    graph.iter_successors(DirectedNodeIndex::from_bidirected(node, forward))
+                {
+                    let next = successor.node().into_bidirected();
+                    if components[next.into_usize()] == usize::MAX {
+                        components[next.into_usize()] = component;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+    }
+
+    let components = components
+        .into_iter()
+        .map(ComponentIndex::from_usize)
+        .collect();
+    (components, component_count)
+}
+
+/// Label the directed nodes with their strongly connected components using Tarjan's algorithm.
+pub(crate) fn strongly_connected_components<IndexType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType>,
+) -> (Vec<StrongComponentIndex<IndexType>>, usize) {
+    let node_count = 2 * graph.node_count();
+    let mut discovery = vec![usize::MAX; node_count];
+    let mut low = vec![usize::MAX; node_count];
+    let mut is_on_stack = vec![false; node_count];
+    let mut stack = Vec::new();
+    let mut time = 0;
+    let mut labels = vec![usize::MAX; node_count];
+    let mut label_count = 0;
+
+    for root in 0..node_count {
+        if discovery[root] != usize::MAX {
+            continue;
+        }
+
+        // Each frame holds a node and its successors that remain to be visited, which are collected when the node is entered.
+        let mut call_stack = vec![(root, None)];
+        while let Some((node, successors)) = call_stack.last_mut() {
+            let node = *node;
+            let successors = successors.get_or_insert_with(|| {
+                discovery[node] = time;
+                low[node] = time;
+                time += 1;
+                stack.push(node);
+                is_on_stack[node] = true;
+                graph
+                    .iter_successors(DirectedNodeIndex::from_usize(node))
+                    .map(|successor| successor.node().into_usize())
+                    .collect::<Vec<_>>()
+            });
+
+            if let Some(successor) = successors.pop() {
+                if discovery[successor] == usize::MAX {
+                    call_stack.push((successor, None));
+                } else if is_on_stack[successor] {
+                    low[node] = low[node].min(discovery[successor]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some((parent, _)) = call_stack.last() {
+                low[*parent] = low[*parent].min(low[node]);
+            }
+            if low[node] == discovery[node] {
+                while let Some(member) = stack.pop() {
+                    is_on_stack[member] = false;
+                    labels[member] = label_count;
+                    if member == node {
+                        break;
+                    }
+                }
+                label_count += 1;
+            }
+        }
+    }
+
+    // Renumber the components in order of their smallest node, which makes the labels independent of the search order.
+    let mut renumbering = vec![usize::MAX; label_count];
+    let mut next_label = 0;
+    let strong_components = labels
+        .iter()
+        .map(|label| {
+            if renumbering[*label] == usize::MAX {
+                renumbering[*label] = next_label;
+                next_label += 1;
+            }
+            StrongComponentIndex::from_usize(renumbering[*label])
+        })
+        .collect();
+
+    (strong_components, label_count)
+}
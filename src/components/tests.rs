@@ -0,0 +1,82 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, NodeIndex},
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    components::{ComponentIndex, ComponentLabels, StrongComponentIndex},
+    location::GfaLocation,
+};
+
+fn graph(
+    names: &[&str],
+    edges: &[(u8, u8)],
+) -> BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> {
+    let nodes = names
+        .iter()
+        .map(|name| PlainGfaNodeData::new(name, "AC"))
+        .collect::<Vec<_>>();
+    let edges = edges.iter().map(|&(from, to)| {
+        BidirectedEdge::new_gfa(DirectedNodeIndex::new(from), DirectedNodeIndex::new(to), 0)
+    });
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+#[test]
+fn test_components() {
+    // A and B form a cycle, C leads to D, and E is isolated.
+    let graph = graph(&["A", "B", "C", "D", "E"], &[(0, 2), (2, 0), (4, 6)]);
+    let labels = ComponentLabels::new(&graph);
+
+    assert_eq!(labels.component_count(), 3);
+    assert_eq!(
+        (0..5)
+            .map(|node| labels.component(NodeIndex::from_usize(node)))
+            .collect::<Vec<_>>(),
+        [0, 0, 1, 1, 2].map(ComponentIndex::from_usize),
+    );
+
+    assert_eq!(labels.strong_component_count(), 8);
+    assert_eq!(
+        (0..10)
+            .map(|node| labels.strong_component(DirectedNodeIndex::from_usize(node)))
+            .collect::<Vec<_>>(),
+        [0, 1, 0, 1, 2, 3, 4, 5, 6, 7].map(StrongComponentIndex::from_usize),
+    );
+
+    assert!(labels.may_reach(0.into(), 3.into()));
+    assert!(!labels.may_reach(0.into(), 4.into()));
+    assert!(
+        labels
+            .shortest_path(
+                &graph,
+                GfaLocation::new(0.into(), 0.into()),
+                GfaLocation::new(4.into(), 1.into()),
+            )
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        labels
+            .shortest_path(
+                &graph,
+                GfaLocation::new(0.into(), 0.into()),
+                GfaLocation::new(2.into(), 1.into()),
+            )
+            .unwrap()
+            .unwrap()
+            .length()
+            .into_usize(),
+        3,
+    );
+}
+
+#[test]
+fn test_hairpin_joins_orientations() {
+    // The hairpin at B lets walks turn around, so all directed nodes reach each other.
+    let graph = graph(&["A", "B"], &[(0, 2), (2, 3), (3, 0), (1, 0)]);
+    let labels = ComponentLabels::new(&graph);
+    assert_eq!(labels.component_count(), 1);
+    assert_eq!(labels.strong_component_count(), 1);
+}
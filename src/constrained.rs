@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger};
+use binary_heap_plus::BinaryHeap;
+
+use crate::{
+    dijkstra::{Label, backtrack_walk, saturation_thresholds, search_state},
+    graph::{SequenceGraph, Successor},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A restriction on the nodes that a path may visit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathConstraint {
+    /// The path never visits both orientations of a bidirected node.
+    ///
+    /// Visiting the same orientation multiple times is allowed.
+    NoInversions,
+
+    /// The path never visits a bidirected node more than once, in any orientation.
+    NoRepeatedNodes,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConstrainedPathError {
+    #[error("the search space exceeded the limit of {0} partial paths")]
+    SearchSpaceExceeded(usize),
+
+    #[error(transparent)]
+    LengthOverflow(#[from] LengthOverflowError),
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct SearchState<IndexType, LengthType> {
+    node: DirectedNodeIndex<IndexType>,
+    visited_nodes: Vec<DirectedNodeIndex<IndexType>>,
+    /// The cost of the state if it is below the saturation threshold of its node.
+    ///
+    /// Such states cannot be merged with cheaper states, because only the more expensive one might reach the target.
+    unsaturated_cost: Option<GfaPathLength<LengthType>>,
+}
+
+/// Find the shortest path from `source` to `target` that satisfies the given constraint.
+///
+/// The search is exact, but since the constraints make the problem NP-hard, it enumerates partial paths.
+/// Partial paths that visit the same set of nodes and end in the same node are merged.
+/// If more than `max_label_count` partial paths are created, the search is aborted with an error.
+/// Hence, this is only suitable for small regions of a graph.
+pub fn constrained_shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: GfaLocation<IndexType, LengthType>,
+    target: GfaLocation<IndexType, LengthType>,
+    constraint: PathConstraint,
+    max_label_count: usize,
+) -> Result<Option<GfaPath<IndexType, LengthType>>, ConstrainedPathError> {
+    // Costs are measured from the source to the end of the node of a label.
+    // A label at the target node reaches the target if its cost is at least this threshold.
+    let target_threshold = graph
+        .node_length(target.node())
+        .checked_sub(target.offset().into_length())?;
+    let saturation_thresholds = saturation_thresholds(graph, [(target.node(), target_threshold)])?;
+
+    let mut labels = vec![Label {
+        entry: Successor::new(source.node(), GfaPathLength::from_usize(0)),
+        predecessor: None,
+        seed_index: 0,
+    }];
+    // The directed nodes visited by the partial path of each label, sorted.
+    let mut visited_node_sets = vec![vec![source.node()]];
+    let mut open_list = BinaryHeap::new_min();
+    let mut closed_list = HashSet::new();
+    open_list.push((
+        graph
+            .node_length(source.node())
+            .checked_sub(source.offset().into_length())?,
+        0,
+    ));
+
+    while let Some((cost, label_index)) = open_list.pop() {
+        let label_node = labels[label_index].entry.node();
+        let state = SearchState {
+            node: label_node,
+            visited_nodes: visited_node_sets[label_index].clone(),
+            unsaturated_cost: search_state(&saturation_thresholds, label_node, cost)
+                .unsaturated_cost,
+        };
+        if !closed_list.insert(state) {
+            continue;
+        }
+
+        if label_node == target.node() && cost >= target_threshold {
+            return Ok(Some(GfaPath::from_walk(
+                backtrack_walk(&labels, label_index),
+                source.offset(),
+                cost - target_threshold,
+                graph,
+            )));
+        }
+
+        // Expand label.
+        for successor in graph.iter_successors(label_node) {
+            let node = successor.node();
+            let visited_nodes = &visited_node_sets[label_index];
+            let is_forbidden = match constraint {
+                PathConstraint::NoInversions => {
+                    visited_nodes.binary_search(&graph.invert(node)).is_ok()
+                }
+                PathConstraint::NoRepeatedNodes => {
+                    visited_nodes.binary_search(&node).is_ok()
+                        || visited_nodes.binary_search(&graph.invert(node)).is_ok()
+                }
+            };
+            if is_forbidden {
+                continue;
+            }
+
+            if labels.len() >= max_label_count {
+                return Err(ConstrainedPathError::SearchSpaceExceeded(max_label_count));
+            }
+
+            let mut visited_nodes = visited_nodes.clone();
+            if let Err(index) = visited_nodes.binary_search(&node) {
+                visited_nodes.insert(index, node);
+            }
+
+            open_list.push((successor.extend_cost(cost, graph)?, labels.len()));
+            labels.push(Label {
+                entry: successor,
+                predecessor: Some(label_index),
+                seed_index: 0,
+            });
+            visited_node_sets.push(visited_nodes);
+        }
+    }
+
+    // Terminated without finding the target.
+    Ok(None)
+}
@@ -0,0 +1,139 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::PlainGfaNodeData,
+};
+
+use crate::{
+    constrained::{ConstrainedPathError, PathConstraint, constrained_shortest_path},
+    location::GfaLocation,
+    path::{GfaPath, PathElement},
+};
+
+#[test]
+fn test_hairpin() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+        PlainGfaNodeData::new("C", "CCC"),
+        PlainGfaNodeData::new("D", "DDDDDDDDDD"),
+    ];
+    let edges =
+        [(0, 2, 0), (2, 3, 0), (3, 4, 0), (0, 6, 0), (6, 4, 0)].map(|(from, to, overlap)| {
+            BidirectedEdge::new_gfa(
+                DirectedNodeIndex::new(from),
+                DirectedNodeIndex::new(to),
+                overlap,
+            )
+        });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let path = constrained_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 3.into()),
+        GfaLocation::new(4.into(), 1.into()),
+        PathConstraint::NoInversions,
+        100,
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![
+        PathElement::new(0.into(), 3.into(), 3.into()),
+        PathElement::new(6.into(), 0.into(), 10.into()),
+        PathElement::new(4.into(), 0.into(), 1.into()),
+    ];
+    let expected_path = GfaPath::new(expected_path, 11.into());
+
+    assert_eq!(path.length(), expected_path.length());
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {path:?}",
+    );
+}
+
+#[test]
+fn test_repeated_node() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+    ];
+    let edges = [(0, 2, 1), (2, 0, 1)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let path = constrained_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 2.into()),
+        GfaLocation::new(0.into(), 1.into()),
+        PathConstraint::NoInversions,
+        100,
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![
+        PathElement::new(0.into(), 2.into(), 3.into()),
+        PathElement::new(2.into(), 1.into(), 3.into()),
+        PathElement::new(0.into(), 1.into(), 1.into()),
+    ];
+    let expected_path = GfaPath::new(expected_path, 3.into());
+
+    assert_eq!(path.length(), expected_path.length());
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {path:?}",
+    );
+
+    let path = constrained_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 2.into()),
+        GfaLocation::new(0.into(), 1.into()),
+        PathConstraint::NoRepeatedNodes,
+        100,
+    )
+    .unwrap();
+    assert!(path.is_none());
+}
+
+#[test]
+fn test_search_space_exceeded() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+        PlainGfaNodeData::new("C", "CCC"),
+        PlainGfaNodeData::new("D", "DDDDDDDDDD"),
+    ];
+    let edges =
+        [(0, 2, 0), (2, 3, 0), (3, 4, 0), (0, 6, 0), (6, 4, 0)].map(|(from, to, overlap)| {
+            BidirectedEdge::new_gfa(
+                DirectedNodeIndex::new(from),
+                DirectedNodeIndex::new(to),
+                overlap,
+            )
+        });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let result = constrained_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 3.into()),
+        GfaLocation::new(4.into(), 1.into()),
+        PathConstraint::NoInversions,
+        2,
+    );
+
+    assert_eq!(
+        result.unwrap_err(),
+        ConstrainedPathError::SearchSpaceExceeded(2)
+    );
+}
@@ -0,0 +1,649 @@
+use std::collections::{HashMap, HashSet};
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex};
+use binary_heap_plus::BinaryHeap;
+
+use crate::{
+    dijkstra,
+    graph::{SequenceGraph, Successor},
+    heap_size::{nested_vec_heap_size, vec_heap_size},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, LengthOverflowError, PathElement, PathItem, WalkItems},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum number of nodes settled by a witness search.
+///
+/// If no witness is found within this limit, a shortcut is added, which is never wrong, but may be superfluous.
+const WITNESS_SETTLE_LIMIT: usize = 64;
+
+/// A contraction hierarchy over the directed nodes of a graph.
+///
+/// Both orientations of a bidirected node are contracted together,
+/// and each edge or shortcut is stored once together with its inverse.
+/// Queries run a bidirectional search that only moves upwards in the hierarchy,
+/// and unpack the shortcuts of the found path into the edges of the graph.
+/// Overlaps must not exceed the lengths of the nodes they enter.
+pub struct ContractionHierarchy<IndexType, LengthType> {
+    edges: Vec<Edge<IndexType, LengthType>>,
+    /// The directed edges leaving each directed node towards nodes of at least the same rank.
+    upward: Vec<Vec<DirectedEdge>>,
+    /// The directed edges entering each directed node from nodes of at least the same rank.
+    downward: Vec<Vec<DirectedEdge>>,
+    shortcut_count: usize,
+}
+
+/// A shortest path whose elements are computed on demand by unpacking the shortcuts of the hierarchy.
+///
+/// Only the shortcuts on the part of the path that is iterated are unpacked.
+/// The elements are the same as those of the path returned by [`ContractionHierarchy::shortest_path`].
+pub struct LazyPath<
+    'hierarchy,
+    'graph,
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+    Graph,
+> {
+    length: GfaPathLength<LengthType>,
+    items: LazyPathItems<'hierarchy, 'graph, IndexType, LengthType, Graph>,
+}
+
+enum LazyPathItems<
+    'hierarchy,
+    'graph,
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+    Graph,
+> {
+    /// The walk found in the hierarchy, unpacked on demand.
+    Unpacking(
+        WalkItems<
+            'graph,
+            Unpacking<'hierarchy, IndexType, LengthType>,
+            Graph,
+            IndexType,
+            LengthType,
+        >,
+    ),
+    /// The path found by the search without the hierarchy.
+    Searched(std::vec::IntoIter<PathItem<IndexType, LengthType>>),
+}
+
+/// The successors that make up a sequence of edges and shortcuts, unpacked one edge at a time.
+struct Unpacking<'hierarchy, IndexType, LengthType> {
+    edges: &'hierarchy [Edge<IndexType, LengthType>],
+    /// The entry of the first node of the walk, which precedes all edges.
+    start: Option<Successor<IndexType, LengthType>>,
+    /// The edges that are not yet unpacked, in reverse order.
+    stack: Vec<DirectedEdge>,
+}
+
+/// An edge or a shortcut, in both of its orientations.
+struct Edge<IndexType, LengthType> {
+    /// The tail and head of the edge, and of its inverse.
+    endpoints: [(DirectedNodeIndex<IndexType>, DirectedNodeIndex<IndexType>); 2],
+    /// The length that traversing the edge or its inverse adds to a path.
+    weights: [GfaPathLength<LengthType>; 2],
+    kind: EdgeKind<IndexType, LengthType>,
+}
+
+enum EdgeKind<IndexType, LengthType> {
+    /// An edge of the graph, given as the successor of its tail and the successor of the tail of its inverse.
+    Original([Successor<IndexType, LengthType>; 2]),
+    /// A shortcut for the given sequence of directed edges.
+    Shortcut(Vec<DirectedEdge>),
+}
+
+/// The distance of each node reached by an upward search, together with the edge that reached it.
+type UpwardSearch<IndexType, LengthType> =
+    HashMap<DirectedNodeIndex<IndexType>, (GfaPathLength<LengthType>, Option<DirectedEdge>)>;
+
+/// One orientation of an [`Edge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct DirectedEdge {
+    index: usize,
+    is_inverse: bool,
+}
+
+/// The graph during contraction.
+struct Contraction<'edges, IndexType, LengthType> {
+    edges: &'edges [Edge<IndexType, LengthType>],
+    outgoing: &'edges [Vec<DirectedEdge>],
+    incoming: &'edges [Vec<DirectedEdge>],
+    is_contracted: &'edges [bool],
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    ContractionHierarchy<IndexType, LengthType>
+{
+    /// Build a contraction hierarchy over the given graph.
+    ///
+    /// Bidirected nodes are contracted in the order of their edge difference,
+    /// i.e. the number of shortcuts their contraction adds minus the number of edges it removes.
+    /// Panics if the length of a shortcut does not fit into the length type.
+    pub fn new(graph: &impl SequenceGraph<IndexType, LengthType = LengthType>) -> Self {
+        let node_count = graph.node_count();
+        let directed_node_count = 2 * node_count;
+
+        let mut edges = Vec::new();
+        for from in (0..directed_node_count).map(DirectedNodeIndex::from_usize) {
+            for successor in graph.iter_successors(from) {
+                let to = successor.node();
+                let inverse_endpoints = (graph.invert(to), graph.invert(from));
+                // Each edge is also found as the inverse of its inverse, so only one of them is stored.
+                if (from, to) > inverse_endpoints {
+                    continue;
+                }
+
+                let inverse = successor.inverse(inverse_endpoints.1);
+                edges.push(Edge {
+                    endpoints: [(from, to), inverse_endpoints],
+                    weights: [successor.weight(graph), inverse.weight(graph)],
+                    kind: EdgeKind::Original([successor, inverse]),
+                });
+            }
+        }
+
+        let mut outgoing = vec![Vec::new(); directed_node_count];
+        let mut incoming = vec![Vec::new(); directed_node_count];
+        for index in 0..edges.len() {
+            insert_edge(&edges, index, &mut outgoing, &mut incoming);
+        }
+
+        let mut is_contracted = vec![false; node_count];
+        let mut rank = vec![0; node_count];
+        let mut queue = BinaryHeap::new_min();
+        for node in 0..node_count {
+            let contraction = Contraction {
+                edges: &edges,
+                outgoing: &outgoing,
+                incoming: &incoming,
+                is_contracted: &is_contracted,
+            };
+            queue.push((contraction.edge_difference(node), node));
+        }
+
+        let mut next_rank = 0;
+        let mut shortcut_count = 0;
+        while let Some((_, node)) = queue.pop() {
+            let contraction = Contraction {
+                edges: &edges,
+                outgoing: &outgoing,
+                incoming: &incoming,
+                is_contracted: &is_contracted,
+            };
+            // Priorities are updated lazily, since contracting a node changes the priorities of its neighbours.
+            let edge_difference = contraction.edge_difference(node);
+            if queue
+                .peek()
+                .is_some_and(|(priority, _)| edge_difference > *priority)
+            {
+                queue.push((edge_difference, node));
+                continue;
+            }
+
+            let shortcuts = contraction.shortcuts(node);
+            is_contracted[node] = true;
+            rank[node] = next_rank;
+            next_rank += 1;
+
+            for path in shortcuts {
+                let inverse_path = invert_path(&path);
+                edges.push(Edge {
+                    endpoints: [
+                        (tail(&edges, path[0]), head(&edges, *path.last().unwrap())),
+                        (
+                            tail(&edges, inverse_path[0]),
+                            head(&edges, *inverse_path.last().unwrap()),
+                        ),
+                    ],
+                    weights: [
+                        path_weight(&edges, &path),
+                        path_weight(&edges, &inverse_path),
+                    ],
+                    kind: EdgeKind::Shortcut(path),
+                });
+                insert_edge(&edges, edges.len() - 1, &mut outgoing, &mut incoming);
+                shortcut_count += 1;
+            }
+        }
+
+        let mut upward = vec![Vec::new(); directed_node_count];
+        let mut downward = vec![Vec::new(); directed_node_count];
+        for index in 0..edges.len() {
+            for is_inverse in [false, true] {
+                let edge = DirectedEdge { index, is_inverse };
+                let (from, to) = (tail(&edges, edge), head(&edges, edge));
+                let from_rank = rank[from.into_bidirected().into_usize()];
+                let to_rank = rank[to.into_bidirected().into_usize()];
+                if to_rank >= from_rank {
+                    upward[from.into_usize()].push(edge);
+                }
+                if from_rank >= to_rank {
+                    downward[to.into_usize()].push(edge);
+                }
+            }
+        }
+
+        Self {
+            edges,
+            upward,
+            downward,
+            shortcut_count,
+        }
+    }
+
+    /// Returns the number of shortcuts added during contraction.
+    ///
+    /// Each shortcut is counted once, even though it is used in both orientations.
+    pub fn shortcut_count(&self) -> usize {
+        self.shortcut_count
+    }
+
+    /// Returns the approximate number of bytes that the hierarchy allocates on the heap.
+    pub fn heap_size(&self) -> usize {
+        let shortcut_size = self
+            .edges
+            .iter()
+            .map(|edge| match &edge.kind {
+                EdgeKind::Original(_) => 0,
+                EdgeKind::Shortcut(edges) => vec_heap_size(edges),
+            })
+            .sum::<usize>();
+        vec_heap_size(&self.edges)
+            + shortcut_size
+            + nested_vec_heap_size(&self.upward)
+            + nested_vec_heap_size(&self.downward)
+    }
+
+    /// Find the shortest path from `source` to `target`.
+    ///
+    /// The result is the same as that of [`shortest_path`](crate::dijkstra::shortest_path) on the graph the hierarchy was built from.
+    /// If the shortest walk between the nodes ends before the target, which can only happen if the target lies before the source within overlaps,
+    /// the query falls back to the search without the hierarchy.
+    /// Returns an error if the length of a walk does not fit into the length type.
+    pub fn shortest_path(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<Option<GfaPath<IndexType, LengthType>>, LengthOverflowError> {
+        let Some(mut path) = self.lazy_shortest_path(graph, source, target)? else {
+            return Ok(None);
+        };
+        let items = std::iter::from_fn(|| path.next_item()).collect();
+        Ok(Some(GfaPath::from_items(items, path.length)))
+    }
+
+    /// Find the shortest path from `source` to `target`, without unpacking its shortcuts yet.
+    ///
+    /// The length of the path is known immediately, while its elements are unpacked as they are iterated.
+    /// If the query falls back to the search without the hierarchy, as described for [`shortest_path`](Self::shortest_path),
+    /// the path is computed completely.
+    pub fn lazy_shortest_path<'graph, Graph: SequenceGraph<IndexType, LengthType = LengthType>>(
+        &self,
+        graph: &'graph Graph,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<Option<LazyPath<'_, 'graph, IndexType, LengthType, Graph>>, LengthOverflowError>
+    {
+        let forward = self.upward_search(source.node(), &self.upward, false)?;
+        let backward = self.upward_search(target.node(), &self.downward, true)?;
+        let meetings = forward
+            .iter()
+            .filter_map(|(node, (forward_length, _))| {
+                backward.get(node).map(|(backward_length, _)| {
+                    Ok((*node, forward_length.checked_add(*backward_length)?))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // Ties are broken by node, since the iteration order of the search results is arbitrary.
+        let Some((meeting_node, walk_length)) = meetings
+            .into_iter()
+            .min_by_key(|(node, length)| (*length, *node))
+        else {
+            return Ok(None);
+        };
+
+        // Costs are measured from the source to the end of the node, as in the search without the hierarchy.
+        let cost = graph
+            .node_length(source.node())
+            .checked_sub(source.offset().into_length())?
+            .checked_add(walk_length)?;
+        let target_threshold = graph
+            .node_length(target.node())
+            .checked_sub(target.offset().into_length())?;
+        if cost < target_threshold {
+            let Some(path) = dijkstra::shortest_path(graph, source, target)? else {
+                return Ok(None);
+            };
+            return Ok(Some(LazyPath {
+                length: path.length(),
+                items: LazyPathItems::Searched(path.iter_items().collect::<Vec<_>>().into_iter()),
+            }));
+        }
+
+        // The stack holds the edges in reverse order, so the edges from the meeting node to the target come first.
+        let mut stack = Vec::new();
+        let mut node = meeting_node;
+        while let Some(edge) = backward[&node].1 {
+            stack.push(edge);
+            node = head(&self.edges, edge);
+        }
+        stack.reverse();
+        let mut node = meeting_node;
+        while let Some(edge) = forward[&node].1 {
+            stack.push(edge);
+            node = tail(&self.edges, edge);
+        }
+
+        let unpacking = Unpacking {
+            edges: &self.edges,
+            start: Some(Successor::new(source.node(), GfaPathLength::from_usize(0))),
+            stack,
+        };
+        let length = cost - target_threshold;
+        Ok(Some(LazyPath {
+            length,
+            items: LazyPathItems::Unpacking(WalkItems::new(
+                unpacking,
+                source.offset(),
+                length,
+                graph,
+            )),
+        }))
+    }
+
+    /// Run a Dijkstra search from `start` over the given adjacency lists.
+    ///
+    /// Returns the distance of each reached node together with the edge that reached it.
+    /// If `is_backward` is set, the edges are traversed from head to tail.
+    fn upward_search(
+        &self,
+        start: DirectedNodeIndex<IndexType>,
+        adjacency: &[Vec<DirectedEdge>],
+        is_backward: bool,
+    ) -> Result<UpwardSearch<IndexType, LengthType>, LengthOverflowError> {
+        let mut labels = vec![(start, None)];
+        let mut open_list = BinaryHeap::new_min();
+        let mut closed_list = HashMap::new();
+        open_list.push((GfaPathLength::from_usize(0), 0));
+
+        while let Some((length, label_index)) = open_list.pop() {
+            let (node, edge) = labels[label_index];
+            if closed_list.contains_key(&node) {
+                continue;
+            }
+            closed_list.insert(node, (length, edge));
+
+            for &edge in &adjacency[node.into_usize()] {
+                let next = if is_backward {
+                    tail(&self.edges, edge)
+                } else {
+                    head(&self.edges, edge)
+                };
+                if !closed_list.contains_key(&next) {
+                    open_list.push((length.checked_add(weight(&self.edges, edge))?, labels.len()));
+                    labels.push((next, Some(edge)));
+                }
+            }
+        }
+
+        Ok(closed_list)
+    }
+}
+
+impl<
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+    Graph: SequenceGraph<IndexType, LengthType = LengthType>,
+> LazyPath<'_, '_, IndexType, LengthType, Graph>
+{
+    pub fn length(&self) -> GfaPathLength<LengthType> {
+        self.length
+    }
+
+    fn next_item(&mut self) -> Option<PathItem<IndexType, LengthType>> {
+        match &mut self.items {
+            LazyPathItems::Unpacking(items) => items.next(),
+            LazyPathItems::Searched(items) => items.next(),
+        }
+    }
+}
+
+/// Iterate over the node elements of the path, skipping gaps.
+impl<
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+    Graph: SequenceGraph<IndexType, LengthType = LengthType>,
+> Iterator for LazyPath<'_, '_, IndexType, LengthType, Graph>
+{
+    type Item = PathElement<IndexType, LengthType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_item()? {
+                PathItem::Node(element) => return Some(element),
+                PathItem::Gap(_) => {}
+            }
+        }
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> Iterator
+    for Unpacking<'_, IndexType, LengthType>
+{
+    type Item = Successor<IndexType, LengthType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(start) = self.start.take() {
+            return Some(start);
+        }
+
+        // Replace shortcuts by their edges until an edge of the graph is on top of the stack.
+        loop {
+            let edge = self.stack.pop()?;
+            match &self.edges[edge.index].kind {
+                EdgeKind::Original(successors) => {
+                    return Some(successors[usize::from(edge.is_inverse)]);
+                }
+                // The inverse of a shortcut traverses its edges inverted and in reverse order,
+                // so they are pushed inverted and in their original order.
+                EdgeKind::Shortcut(path) if edge.is_inverse => {
+                    self.stack.extend(path.iter().map(|edge| DirectedEdge {
+                        index: edge.index,
+                        is_inverse: !edge.is_inverse,
+                    }));
+                }
+                EdgeKind::Shortcut(path) => self.stack.extend(path.iter().rev()),
+            }
+        }
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    Contraction<'_, IndexType, LengthType>
+{
+    /// The number of shortcuts added by contracting the node minus the number of edges removed by it.
+    fn edge_difference(&self, node: usize) -> isize {
+        let removed_edge_count = cluster::<IndexType>(node)
+            .into_iter()
+            .map(|node| {
+                self.outgoing[node.into_usize()]
+                    .iter()
+                    .filter(|edge| self.is_live(head(self.edges, **edge)))
+                    .count()
+            })
+            .sum::<usize>();
+        self.shortcuts(node).len() as isize - removed_edge_count as isize
+    }
+
+    /// Find the shortcuts required to contract the bidirected node.
+    ///
+    /// Paths may enter and leave the node in either orientation, and traverse an edge between its orientations.
+    /// Of each shortcut and its inverse, only one is returned.
+    fn shortcuts(&self, node: usize) -> Vec<Vec<DirectedEdge>> {
+        let is_outside = |other: DirectedNodeIndex<IndexType>| {
+            other.into_bidirected().into_usize() != node && self.is_live(other)
+        };
+
+        let mut shortcuts = Vec::new();
+        for entry in cluster(node) {
+            for &in_edge in &self.incoming[entry.into_usize()] {
+                let from = tail(self.edges, in_edge);
+                if !is_outside(from) {
+                    continue;
+                }
+
+                for exit in cluster(node) {
+                    // All paths inside the node, so that the inverse of each path is enumerated as well.
+                    let mut inner_paths = Vec::new();
+                    if entry == exit {
+                        inner_paths.push(None);
+                    }
+                    inner_paths.extend(
+                        self.outgoing[entry.into_usize()]
+                            .iter()
+                            .filter(|edge| head(self.edges, **edge) == exit)
+                            .map(|edge| Some(*edge)),
+                    );
+
+                    for inner_path in inner_paths {
+                        for &out_edge in &self.outgoing[exit.into_usize()] {
+                            let to = head(self.edges, out_edge);
+                            if !is_outside(to) || to == from {
+                                continue;
+                            }
+
+                            let path = [Some(in_edge), inner_path, Some(out_edge)]
+                                .into_iter()
+                                .flatten()
+                                .collect::<Vec<_>>();
+                            let inverse_path = invert_path(&path);
+                            if path > inverse_path {
+                                continue;
+                            }
+
+                            let has_witness =
+                                self.has_witness(from, to, path_weight(self.edges, &path), node)
+                                    && self.has_witness(
+                                        tail(self.edges, inverse_path[0]),
+                                        head(self.edges, *inverse_path.last().unwrap()),
+                                        path_weight(self.edges, &inverse_path),
+                                        node,
+                                    );
+                            if !has_witness {
+                                shortcuts.push(path);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        shortcuts
+    }
+
+    /// Check if there is a path from `from` to `to` of at most `max_length` that avoids the given bidirected node.
+    fn has_witness(
+        &self,
+        from: DirectedNodeIndex<IndexType>,
+        to: DirectedNodeIndex<IndexType>,
+        max_length: GfaPathLength<LengthType>,
+        avoided_node: usize,
+    ) -> bool {
+        let mut open_list = BinaryHeap::new_min();
+        let mut closed_list = HashSet::new();
+        open_list.push((GfaPathLength::from_usize(0), from));
+
+        while let Some((length, node)) = open_list.pop() {
+            if length > max_length || closed_list.len() >= WITNESS_SETTLE_LIMIT {
+                return false;
+            }
+            if node == to {
+                return true;
+            }
+            if !closed_list.insert(node) {
+                continue;
+            }
+
+            for &edge in &self.outgoing[node.into_usize()] {
+                let next = head(self.edges, edge);
+                if next.into_bidirected().into_usize() != avoided_node
+                    && self.is_live(next)
+                    && !closed_list.contains(&next)
+                {
+                    open_list.push((length + weight(self.edges, edge), next));
+                }
+            }
+        }
+
+        false
+    }
+
+    fn is_live(&self, node: DirectedNodeIndex<IndexType>) -> bool {
+        !self.is_contracted[node.into_bidirected().into_usize()]
+    }
+}
+
+/// Both orientations of the bidirected node.
+fn cluster<IndexType: GraphIndexInteger>(node: usize) -> [DirectedNodeIndex<IndexType>; 2] {
+    [true, false]
+        .map(|forward| DirectedNodeIndex::from_bidirected(NodeIndex::from_usize(node), forward))
+}
+
+fn insert_edge<IndexType: GraphIndexInteger, LengthType>(
+    edges: &[Edge<IndexType, LengthType>],
+    index: usize,
+    outgoing: &mut [Vec<DirectedEdge>],
+    incoming: &mut [Vec<DirectedEdge>],
+) {
+    for is_inverse in [false, true] {
+        let edge = DirectedEdge { index, is_inverse };
+        outgoing[tail(edges, edge).into_usize()].push(edge);
+        incoming[head(edges, edge).into_usize()].push(edge);
+    }
+}
+
+fn invert_path(path: &[DirectedEdge]) -> Vec<DirectedEdge> {
+    path.iter()
+        .rev()
+        .map(|edge| DirectedEdge {
+            index: edge.index,
+            is_inverse: !edge.is_inverse,
+        })
+        .collect()
+}
+
+fn tail<IndexType: GraphIndexInteger, LengthType>(
+    edges: &[Edge<IndexType, LengthType>],
+    edge: DirectedEdge,
+) -> DirectedNodeIndex<IndexType> {
+    edges[edge.index].endpoints[usize::from(edge.is_inverse)].0
+}
+
+fn head<IndexType: GraphIndexInteger, LengthType>(
+    edges: &[Edge<IndexType, LengthType>],
+    edge: DirectedEdge,
+) -> DirectedNodeIndex<IndexType> {
+    edges[edge.index].endpoints[usize::from(edge.is_inverse)].1
+}
+
+fn weight<IndexType, LengthType: GraphIndexInteger>(
+    edges: &[Edge<IndexType, LengthType>],
+    edge: DirectedEdge,
+) -> GfaPathLength<LengthType> {
+    edges[edge.index].weights[usize::from(edge.is_inverse)]
+}
+
+fn path_weight<IndexType, LengthType: GraphIndexInteger>(
+    edges: &[Edge<IndexType, LengthType>],
+    path: &[DirectedEdge],
+) -> GfaPathLength<LengthType> {
+    path.iter()
+        .fold(GfaPathLength::from_usize(0), |length, edge| {
+            length + weight(edges, *edge)
+        })
+}
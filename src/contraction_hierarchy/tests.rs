@@ -0,0 +1,179 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    contraction_hierarchy::ContractionHierarchy,
+    dijkstra::shortest_path,
+    graph::SequenceGraph,
+    location::{GfaLocation, GfaNodeOffset},
+    path::GfaPathLength,
+    test_util::{assert_same_distances, random_graph},
+};
+
+/// The length of the path of the hierarchy from `source` to `target`, after checking that the path connects them.
+fn checked_path_length(
+    hierarchy: &ContractionHierarchy<u16, u64>,
+    graph: &BidirectedAdjacencyArray<u16, PlainGfaNodeData, PlainGfaEdgeData>,
+    source: GfaLocation<u16, u64>,
+    target: GfaLocation<u16, u64>,
+) -> Option<GfaPathLength<u64>> {
+    let path = hierarchy.shortest_path(graph, source, target).unwrap()?;
+    let elements = path.iter().collect::<Vec<_>>();
+    assert_eq!(elements.first().unwrap().node(), source.node());
+    assert_eq!(elements.first().unwrap().offset(), source.offset());
+    assert_eq!(elements.last().unwrap().node(), target.node());
+    assert_eq!(elements.last().unwrap().limit(), target.offset());
+    assert_eq!(
+        elements
+            .iter()
+            .map(|element| element.length().into_usize())
+            .sum::<usize>(),
+        path.length().into_usize(),
+    );
+    Some(path.length())
+}
+
+#[test]
+fn test_hairpin() {
+    // A path from A to C must turn around at the hairpin of B.
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBBB"),
+        PlainGfaNodeData::new("C", "CC"),
+    ];
+    let edges = [(0, 2, 1), (2, 3, 2), (3, 4, 0)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u16, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let hierarchy = ContractionHierarchy::new(&graph);
+    let path = hierarchy
+        .shortest_path(
+            &graph,
+            GfaLocation::new(0.into(), 0.into()),
+            GfaLocation::new(4.into(), 2.into()),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        path.iter()
+            .map(|element| element.node())
+            .collect::<Vec<_>>(),
+        [0.into(), 2.into(), 3.into(), 4.into()],
+    );
+    assert_same_distances(&graph, |source, target| {
+        checked_path_length(&hierarchy, &graph, source, target)
+    });
+}
+
+#[test]
+fn test_random_graphs() {
+    let mut shortcut_count = 0;
+    for seed in 0..4 {
+        let graph = random_graph(seed, 16, 32);
+        let hierarchy = ContractionHierarchy::new(&graph);
+        assert_same_distances(&graph, |source, target| {
+            checked_path_length(&hierarchy, &graph, source, target)
+        });
+        shortcut_count += hierarchy.shortcut_count();
+    }
+    assert!(shortcut_count > 0);
+}
+
+#[test]
+fn test_lazy_paths() {
+    for seed in 0..2 {
+        let graph = random_graph(seed, 16, 32);
+        let hierarchy = ContractionHierarchy::new(&graph);
+        let locations = (0..2 * graph.node_count())
+            .map(DirectedNodeIndex::<u16>::from_usize)
+            .map(|node| GfaLocation::new(node, GfaNodeOffset::from_usize(0)))
+            .collect::<Vec<_>>();
+
+        for source in &locations {
+            for target in &locations {
+                let Some(path) = hierarchy.shortest_path(&graph, *source, *target).unwrap() else {
+                    assert!(
+                        hierarchy
+                            .lazy_shortest_path(&graph, *source, *target)
+                            .unwrap()
+                            .is_none()
+                    );
+                    continue;
+                };
+                let elements = path.iter().collect::<Vec<_>>();
+
+                let lazy_path = hierarchy
+                    .lazy_shortest_path(&graph, *source, *target)
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(lazy_path.length(), path.length());
+                assert_eq!(lazy_path.collect::<Vec<_>>(), elements);
+
+                // Taking a prefix only unpacks the shortcuts it needs, but yields the same elements.
+                let prefix = hierarchy
+                    .lazy_shortest_path(&graph, *source, *target)
+                    .unwrap()
+                    .unwrap()
+                    .take(2)
+                    .collect::<Vec<_>>();
+                assert_eq!(prefix, elements[..elements.len().min(2)]);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_lazy_paths_with_overlaps() {
+    // A cycle in which C is covered by the overlap from B, so each pair of locations is connected by a single shortest walk.
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+        PlainGfaNodeData::new("C", "CCC"),
+    ];
+    let edges = [(0, 2, 2), (2, 4, 3), (4, 0, 2)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u16, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+    let hierarchy = ContractionHierarchy::new(&graph);
+    let locations = (0..2 * graph.node_count())
+        .map(DirectedNodeIndex::<u16>::from_usize)
+        .flat_map(|node| {
+            (0..=graph.node_length(node).into_usize())
+                .map(move |offset| GfaLocation::new(node, GfaNodeOffset::from_usize(offset)))
+        })
+        .collect::<Vec<_>>();
+
+    for source in &locations {
+        for target in &locations {
+            let path = shortest_path(&graph, *source, *target).unwrap();
+            let lazy_path = hierarchy
+                .lazy_shortest_path(&graph, *source, *target)
+                .unwrap();
+            assert_eq!(lazy_path.is_some(), path.is_some());
+            let (Some(path), Some(lazy_path)) = (path, lazy_path) else {
+                continue;
+            };
+
+            assert_eq!(lazy_path.length(), path.length());
+            assert_eq!(
+                lazy_path.collect::<Vec<_>>(),
+                path.iter().collect::<Vec<_>>(),
+                "Paths differ from {source:?} to {target:?}",
+            );
+        }
+    }
+}
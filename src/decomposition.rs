@@ -0,0 +1,289 @@
+//! Queries on the SPQR decomposition of a graph, in terms of the directed nodes used by the rest of this crate.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bidirected_adjacency_array::{
+    graph::BidirectedAdjacencyArray,
+    index::{DirectedNodeIndex, EdgeIndex, GraphIndexInteger, NodeIndex},
+};
+use spqr_tree::{
+    decomposition::{
+        SPQRDecomposition, SPQRNode, SPQRNodeType,
+        indices::{BlockIndex, SPQRNodeIndex},
+    },
+    graph::{NamedNodeData, StaticGraph},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The directed nodes connected by an edge, in one direction of traversal.
+type NodePair<IndexType> = (DirectedNodeIndex<IndexType>, DirectedNodeIndex<IndexType>);
+
+/// Lookup tables over an SPQR decomposition.
+///
+/// Graph edges are addressed by the directed nodes they connect, in either direction of traversal,
+/// such that parallel edges are addressed together.
+/// Edges that are not part of any SPQR node, such as bridges, are not found.
+pub struct DecompositionLookup<
+    'decomposition,
+    'graph,
+    IndexType: GraphIndexInteger,
+    NodeData,
+    EdgeData,
+> where
+    NodeData: NamedNodeData,
+{
+    decomposition: &'decomposition SPQRDecomposition<
+        'graph,
+        BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    >,
+    edges: HashMap<NodePair<IndexType>, Vec<EdgeIndex<IndexType>>>,
+    edge_spqr_nodes: HashMap<EdgeIndex<IndexType>, SPQRNodeIndex<IndexType>>,
+    node_spqr_nodes: HashMap<NodeIndex<IndexType>, Vec<SPQRNodeIndex<IndexType>>>,
+    spqr_nodes: HashMap<
+        SPQRNodeIndex<IndexType>,
+        &'decomposition SPQRNode<BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>>,
+    >,
+    blocks: Vec<BlockIndex<IndexType>>,
+}
+
+impl<'decomposition, 'graph, IndexType: GraphIndexInteger, NodeData: NamedNodeData, EdgeData>
+    DecompositionLookup<'decomposition, 'graph, IndexType, NodeData, EdgeData>
+{
+    pub fn new(
+        decomposition: &'decomposition SPQRDecomposition<
+            'graph,
+            BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+        >,
+    ) -> Self {
+        let graph = decomposition.graph();
+        let mut edges = HashMap::<_, Vec<_>>::new();
+        for edge_index in graph.iter_edges() {
+            let edge = graph.edge(edge_index);
+            edges
+                .entry((edge.from(), edge.to()))
+                .or_default()
+                .push(edge_index);
+            // An edge that is its own reverse complement is addressed only once.
+            let inverted = (edge.to().invert(), edge.from().invert());
+            if inverted != (edge.from(), edge.to()) {
+                edges.entry(inverted).or_default().push(edge_index);
+            }
+        }
+
+        let blocks = decomposition
+            .iter_components()
+            .flat_map(|(component_index, _)| {
+                decomposition.iter_blocks_in_component(component_index)
+            })
+            .map(|(block_index, _)| block_index)
+            .collect::<Vec<_>>();
+
+        let mut lookup = Self {
+            decomposition,
+            edges,
+            edge_spqr_nodes: HashMap::new(),
+            node_spqr_nodes: HashMap::new(),
+            spqr_nodes: HashMap::new(),
+            blocks,
+        };
+        for (spqr_node_index, spqr_node) in lookup
+            .blocks
+            .iter()
+            .flat_map(|block| decomposition.iter_spqr_nodes_in_block(*block))
+        {
+            lookup
+                .edge_spqr_nodes
+                .extend(spqr_node.iter_edges().map(|edge| (edge, spqr_node_index)));
+            for node in spqr_node.iter_nodes() {
+                lookup
+                    .node_spqr_nodes
+                    .entry(node)
+                    .or_default()
+                    .push(spqr_node_index);
+            }
+            lookup.spqr_nodes.insert(spqr_node_index, spqr_node);
+        }
+        for spqr_nodes in lookup.node_spqr_nodes.values_mut() {
+            spqr_nodes.sort_unstable();
+        }
+
+        lookup
+    }
+
+    pub fn decomposition(
+        &self,
+    ) -> &'decomposition SPQRDecomposition<
+        'graph,
+        BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    > {
+        self.decomposition
+    }
+
+    /// Returns the SPQR nodes whose skeletons contain an edge from `from` to `to` as a real edge, sorted by index.
+    ///
+    /// There is more than one such SPQR node only if there are parallel edges that are placed into different skeletons.
+    pub fn spqr_nodes_of_edge(
+        &self,
+        from: DirectedNodeIndex<IndexType>,
+        to: DirectedNodeIndex<IndexType>,
+    ) -> Vec<SPQRNodeIndex<IndexType>> {
+        let mut spqr_nodes = self
+            .edges
+            .get(&(from, to))
+            .into_iter()
+            .flatten()
+            .filter_map(|edge| self.edge_spqr_nodes.get(edge).copied())
+            .collect::<Vec<_>>();
+        spqr_nodes.sort_unstable();
+        spqr_nodes.dedup();
+        spqr_nodes
+    }
+
+    /// Returns the SPQR nodes whose skeletons contain the node, independently of its orientation, sorted by index.
+    ///
+    /// A node is contained in several SPQR nodes if it is part of a separation pair or a cut node.
+    pub fn spqr_nodes_of_node(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> &[SPQRNodeIndex<IndexType>] {
+        self.node_spqr_nodes
+            .get(&node.into_bidirected())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the SPQR node with the given index.
+    ///
+    /// Panics if the SPQR node does not exist.
+    pub fn spqr_node(
+        &self,
+        spqr_node: SPQRNodeIndex<IndexType>,
+    ) -> &'decomposition SPQRNode<BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>> {
+        self.spqr_nodes
+            .get(&spqr_node)
+            .unwrap_or_else(|| panic!("SPQR node {spqr_node} does not exist"))
+    }
+
+    /// Returns the block that contains the SPQR node.
+    pub fn block_of_spqr_node(&self, spqr_node: SPQRNodeIndex<IndexType>) -> BlockIndex<IndexType> {
+        self.spqr_node(spqr_node).block()
+    }
+
+    /// Iterate over all blocks in order of components.
+    pub fn iter_blocks(&self) -> impl Iterator<Item = BlockIndex<IndexType>> {
+        self.blocks.iter().copied()
+    }
+
+    /// Returns all separation pairs of the block, sorted.
+    ///
+    /// These are the poles of the virtual edges, and the non-adjacent nodes of the cycles of S-nodes.
+    /// Each pair is ordered by node index.
+    pub fn separation_pairs(
+        &self,
+        block: BlockIndex<IndexType>,
+    ) -> Vec<(NodeIndex<IndexType>, NodeIndex<IndexType>)> {
+        let ordered = |(u, v): (NodeIndex<IndexType>, NodeIndex<IndexType>)| (u.min(v), u.max(v));
+        let mut separation_pairs = self
+            .decomposition
+            .iter_spqr_edges_in_block(block)
+            .map(|(_, spqr_edge)| ordered(spqr_edge.virtual_edge()))
+            .collect::<BTreeSet<_>>();
+
+        for (spqr_node_index, spqr_node) in self.decomposition.iter_spqr_nodes_in_block(block) {
+            if spqr_node.spqr_node_type() != SPQRNodeType::SNode {
+                continue;
+            }
+
+            let cycle = self.s_node_cycle(block, spqr_node_index);
+            for (first_position, first) in cycle.iter().enumerate() {
+                // Skip the neighbours in the cycle, including the predecessor of the first node.
+                let last_position = cycle.len() - usize::from(first_position == 0);
+                for second in &cycle[(first_position + 2).min(last_position)..last_position] {
+                    separation_pairs.insert(ordered((*first, *second)));
+                }
+            }
+        }
+
+        separation_pairs.into_iter().collect()
+    }
+
+    /// Returns the SPQR nodes of the block in depth-first preorder, each with its parent in the tree.
+    ///
+    /// The tree is rooted at the SPQR node with the smallest index, and children are visited in order of their indices,
+    /// so the order does not depend on the order of the lines of the decomposition file.
+    pub fn iter_tree(
+        &self,
+        block: BlockIndex<IndexType>,
+    ) -> impl Iterator<Item = (SPQRNodeIndex<IndexType>, Option<SPQRNodeIndex<IndexType>>)> {
+        let mut neighbours = BTreeMap::<_, BTreeSet<_>>::new();
+        for (spqr_node_index, _) in self.decomposition.iter_spqr_nodes_in_block(block) {
+            neighbours.entry(spqr_node_index).or_default();
+        }
+        for (_, spqr_edge) in self.decomposition.iter_spqr_edges_in_block(block) {
+            let (from, to) = spqr_edge.endpoints();
+            neighbours.entry(from).or_default().insert(to);
+            neighbours.entry(to).or_default().insert(from);
+        }
+
+        let mut order = Vec::new();
+        let mut stack = Vec::from_iter(neighbours.keys().next().map(|root| (*root, None)));
+        while let Some((spqr_node, parent)) = stack.pop() {
+            order.push((spqr_node, parent));
+            stack.extend(
+                neighbours[&spqr_node]
+                    .iter()
+                    .rev()
+                    .filter(|child| Some(**child) != parent)
+                    .map(|child| (*child, Some(spqr_node))),
+            );
+        }
+        order.into_iter()
+    }
+
+    /// Returns the nodes of the skeleton of an S-node in the order of its cycle, starting at the smallest node.
+    fn s_node_cycle(
+        &self,
+        block: BlockIndex<IndexType>,
+        spqr_node_index: SPQRNodeIndex<IndexType>,
+    ) -> Vec<NodeIndex<IndexType>> {
+        let graph = self.decomposition.graph();
+        let spqr_node = self.spqr_node(spqr_node_index);
+
+        let virtual_edges = self
+            .decomposition
+            .iter_spqr_edges_in_block(block)
+            .filter(|(_, spqr_edge)| {
+                let (from, to) = spqr_edge.endpoints();
+                from == spqr_node_index || to == spqr_node_index
+            })
+            .map(|(_, spqr_edge)| spqr_edge.virtual_edge());
+        let mut neighbours = HashMap::<_, Vec<_>>::new();
+        for (u, v) in spqr_node
+            .iter_edges()
+            .map(|edge| graph.edge_endpoints(edge))
+            .chain(virtual_edges)
+        {
+            neighbours.entry(u).or_default().push(v);
+            neighbours.entry(v).or_default().push(u);
+        }
+
+        let Some(start) = spqr_node.iter_nodes().min() else {
+            return Vec::new();
+        };
+        let mut cycle = vec![start];
+        let mut previous = start;
+        let mut current = neighbours[&start][0];
+        while current != start {
+            cycle.push(current);
+            let next = *neighbours[&current]
+                .iter()
+                .find(|next| **next != previous)
+                .unwrap();
+            previous = current;
+            current = next;
+        }
+        cycle
+    }
+}
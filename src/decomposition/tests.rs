@@ -0,0 +1,157 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, NodeIndex},
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+use spqr_tree::{
+    decomposition::{
+        SPQRNodeType,
+        builder::SPQRDecompositionBuilder,
+        indices::{BlockIndex, SPQRNodeIndex},
+    },
+    io::plain_spqr_file::read_plain_spqr,
+};
+
+use crate::decomposition::DecompositionLookup;
+
+fn graph(
+    names: &[&str],
+    edges: &[(u8, u8)],
+) -> BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> {
+    let nodes = names
+        .iter()
+        .map(|name| PlainGfaNodeData::new(name, "AC"))
+        .collect::<Vec<_>>();
+    let edges = edges.iter().map(|&(from, to)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(2 * from),
+            DirectedNodeIndex::new(2 * to),
+            0,
+        )
+    });
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+fn spqr_node(index: usize) -> SPQRNodeIndex<u8> {
+    SPQRNodeIndex::from_usize(index)
+}
+
+#[test]
+fn test_cycle_with_chord() {
+    // The cycle A, B, C, D with the chord A, C.
+    let graph = graph(
+        &["A", "B", "C", "D"],
+        &[(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)],
+    );
+    let spqr = "H v0.1 https://github.com/sebschmi/SPQR-tree-file-format
+G G0 A B C D
+B B0 G0 A B C D
+S S0 B0 A B C
+E E0 S0 B0 A B
+E E1 S0 B0 B C
+S S1 B0 C D A
+E E2 S1 B0 C D
+E E3 S1 B0 D A
+P P2 B0 A C
+E E4 P2 B0 A C
+V V0 S0 P2 A C
+V V1 S1 P2 A C
+";
+    let decomposition = read_plain_spqr(&graph, &mut spqr.as_bytes()).unwrap();
+    let lookup = DecompositionLookup::new(&decomposition);
+
+    assert_eq!(
+        lookup.spqr_nodes_of_edge(0.into(), 2.into()),
+        [spqr_node(0)]
+    );
+    assert_eq!(
+        lookup.spqr_nodes_of_edge(3.into(), 1.into()),
+        [spqr_node(0)]
+    );
+    assert_eq!(
+        lookup.spqr_nodes_of_edge(0.into(), 4.into()),
+        [spqr_node(2)]
+    );
+    assert_eq!(lookup.spqr_nodes_of_edge(0.into(), 3.into()), []);
+
+    assert_eq!(
+        lookup.spqr_nodes_of_node(1.into()),
+        [spqr_node(0), spqr_node(1), spqr_node(2)],
+    );
+    assert_eq!(lookup.spqr_nodes_of_node(2.into()), [spqr_node(0)]);
+
+    let block = BlockIndex::from_usize(0);
+    assert_eq!(lookup.block_of_spqr_node(spqr_node(1)), block);
+    assert_eq!(
+        lookup.separation_pairs(block),
+        [(NodeIndex::from_usize(0), NodeIndex::from_usize(2))],
+    );
+    assert_eq!(
+        lookup.iter_tree(block).collect::<Vec<_>>(),
+        [
+            (spqr_node(0), None),
+            (spqr_node(2), Some(spqr_node(0))),
+            (spqr_node(1), Some(spqr_node(2))),
+        ],
+    );
+}
+
+#[test]
+fn test_cycle() {
+    // Each pair of opposite nodes of the cycle is a separation pair.
+    let graph = graph(&["A", "B", "C", "D"], &[(0, 1), (1, 2), (2, 3), (3, 0)]);
+    let spqr = "H v0.1 https://github.com/sebschmi/SPQR-tree-file-format
+G G0 A B C D
+B B0 G0 A B C D
+S S0 B0 A B C D
+E E0 S0 B0 A B
+E E1 S0 B0 B C
+E E2 S0 B0 C D
+E E3 S0 B0 D A
+";
+    let decomposition = read_plain_spqr(&graph, &mut spqr.as_bytes()).unwrap();
+    let lookup = DecompositionLookup::new(&decomposition);
+
+    let block = BlockIndex::from_usize(0);
+    assert_eq!(
+        lookup.separation_pairs(block),
+        [
+            (NodeIndex::from_usize(0), NodeIndex::from_usize(2)),
+            (NodeIndex::from_usize(1), NodeIndex::from_usize(3)),
+        ],
+    );
+    assert_eq!(
+        lookup.iter_tree(block).collect::<Vec<_>>(),
+        [(spqr_node(0), None)],
+    );
+}
+
+#[test]
+fn test_parallel_edges() {
+    // The cycle A, B, C with a parallel edge from A to B that is placed into a P-node of its own.
+    // The plain SPQR file format cannot address parallel edges, so the decomposition is built directly.
+    let graph = graph(&["A", "B", "C"], &[(0, 1), (0, 1), (1, 2), (2, 0)]);
+    let [a, b, c] = [0, 1, 2].map(NodeIndex::from_usize);
+    let mut builder = SPQRDecompositionBuilder::new(&graph);
+    let component = builder.add_component(vec![a, b, c]);
+    let block = builder.add_block(component, vec![a, b, c]);
+    let s_node = builder.add_spqr_node(block, vec![a, b, c], SPQRNodeType::SNode);
+    let p_node = builder.add_spqr_node(block, vec![a, b], SPQRNodeType::PNode);
+    for edge in [1, 2, 3] {
+        builder.add_edge_to_spqr_node(edge.into(), s_node);
+    }
+    builder.add_edge_to_spqr_node(0.into(), p_node);
+    builder.add_spqr_edge(Some(block).into(), (s_node, p_node), (a, b));
+    let decomposition = builder.build();
+    let lookup = DecompositionLookup::new(&decomposition);
+
+    assert_eq!(
+        lookup.spqr_nodes_of_edge(0.into(), 2.into()),
+        [s_node, p_node]
+    );
+    assert_eq!(
+        lookup.spqr_nodes_of_edge(3.into(), 1.into()),
+        [s_node, p_node]
+    );
+    assert_eq!(lookup.spqr_nodes_of_edge(2.into(), 4.into()), [s_node]);
+}
@@ -1,156 +1,748 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use bidirected_adjacency_array::{
-    graph::BidirectedAdjacencyArray,
-    index::{DirectedNodeIndex, GraphIndexInteger, OptionalDirectedNodeIndex},
-    io::gfa1::{GfaEdgeData, GfaNodeData},
-};
-use binary_heap_plus::BinaryHeap;
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger};
+use binary_heap_plus::{BinaryHeap, MinComparator};
 
 use crate::{
-    gfa_graph_extensions::GfaNodeDataExt,
-    location::{GfaLocation, GfaNodeOffset},
-    path::{GfaPath, GfaPathLength, PathElement},
+    graph::{SequenceGraph, Successor},
+    location::{GfaLocation, GfaLocationRange, GfaNodeOffset},
+    path::{GfaPath, GfaPathLength, LengthOverflowError},
 };
 
 #[cfg(test)]
 mod tests;
 
-#[derive(Debug, Eq, PartialEq)]
-struct OpenNode<IndexType: GraphIndexInteger> {
+/// The orientations in which a strand-agnostic query was answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrandCombination {
+    /// From the source to the target.
+    SourceToTarget,
+    /// From the source to the inverted target.
+    ///
+    /// This is equivalent to the path from the target to the inverted source.
+    SourceToInvertedTarget,
+    /// From the inverted source to the target.
+    ///
+    /// This is equivalent to the path from the inverted target to the source.
+    InvertedSourceToTarget,
+    /// From the target to the source.
+    ///
+    /// This is equivalent to the path from the inverted source to the inverted target.
+    TargetToSource,
+}
+
+/// The result of a strand-agnostic shortest path query.
+pub struct StrandAgnosticPath<IndexType, LengthType> {
+    path: GfaPath<IndexType, LengthType>,
+    combination: StrandCombination,
+}
+
+/// The result of a shortest path query between sets of locations.
+pub(crate) struct MultiShortestPath<IndexType, LengthType> {
+    pub(crate) source_index: usize,
+    pub(crate) target_index: usize,
+    /// The edges that enter each directed node of the path.
+    walk: Vec<Successor<IndexType, LengthType>>,
+    source_offset: GfaNodeOffset<LengthType>,
+    target_offset: GfaNodeOffset<LengthType>,
+    length: GfaPathLength<LengthType>,
+}
+
+/// The result of a shortest path query between sets of location ranges.
+pub struct SetToSetPath<IndexType, LengthType> {
+    path: GfaPath<IndexType, LengthType>,
+    source_index: usize,
+    target_index: usize,
+}
+
+pub(crate) struct Label<IndexType, LengthType> {
+    /// The edge that enters the node of the label.
+    pub(crate) entry: Successor<IndexType, LengthType>,
+    pub(crate) predecessor: Option<usize>,
+    pub(crate) seed_index: usize,
+}
+
+struct TargetThresholds<LengthType> {
+    target_index: usize,
+    lower: GfaPathLength<LengthType>,
+    upper: GfaPathLength<LengthType>,
+}
+
+/// The targets of [`multi_shortest_paths`], together with the data that the search derives from them.
+///
+/// This only depends on the targets and the graph, so it can be reused by searches from different sources.
+pub(crate) struct SearchTargets<IndexType, LengthType> {
+    targets: Vec<GfaLocationRange<IndexType, LengthType>>,
+    /// The thresholds of the targets on each target node.
+    target_thresholds: HashMap<DirectedNodeIndex<IndexType>, Vec<TargetThresholds<LengthType>>>,
+    /// The maximum upper threshold of each target node.
+    max_upper_thresholds: HashMap<DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>>,
+    /// A label that is expanded into a target node reaches its target with a distance of at least its cost minus this slack.
+    /// It is the maximum amount by which an overlap entering a target node reaches behind the target.
+    entry_slack: GfaPathLength<LengthType>,
+    saturation_thresholds: SaturationThresholds<IndexType, LengthType>,
+}
+
+/// A path to a target found by [`multi_shortest_paths`], given by its distance, target index, label and cost.
+type Candidate<LengthType> = (
+    GfaPathLength<LengthType>,
+    usize,
+    usize,
+    GfaPathLength<LengthType>,
+);
+
+/// The saturation threshold of each node near a target, see [`saturation_thresholds`].
+pub(crate) type SaturationThresholds<IndexType, LengthType> =
+    HashMap<DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>>;
+
+#[derive(PartialEq, Eq, Hash)]
+pub(crate) struct SearchState<IndexType, LengthType> {
     node: DirectedNodeIndex<IndexType>,
-    cost: GfaPathLength<IndexType>,
-    predecessor: OptionalDirectedNodeIndex<IndexType>,
+    /// The cost of the state if it is below the saturation threshold of its node.
+    ///
+    /// Such states cannot be merged with cheaper states, because only the more expensive one might reach a target.
+    pub(crate) unsaturated_cost: Option<GfaPathLength<LengthType>>,
+}
+
+/// Find the shortest path from `source` to `target`.
+///
+/// This is Dijkstra's algorithm where each directed node is settled once with its cheapest label.
+/// The only exception are targets that lie inside an overlap, which a walk may enter behind the target location:
+/// near such targets, more expensive labels are kept until they cannot end before the target anymore.
+///
+/// Overlaps must not exceed the lengths of the nodes they enter,
+/// see [`checked_shortest_path`](crate::overlap::checked_shortest_path) for graphs where they might.
+/// Returns an error if a cost of the search does not fit into the length type.
+pub fn shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: GfaLocation<IndexType, LengthType>,
+    target: GfaLocation<IndexType, LengthType>,
+) -> Result<Option<GfaPath<IndexType, LengthType>>, LengthOverflowError> {
+    // Costs are measured from the source to the end of the node of a label.
+    // A label at the target node reaches the target if its cost is at least the target threshold.
+    let target_threshold = graph
+        .node_length(target.node())
+        .checked_sub(target.offset().into_length())?;
+    let saturation_thresholds = saturation_thresholds(graph, [(target.node(), target_threshold)])?;
+
+    let mut labels = vec![Label {
+        entry: Successor::new(source.node(), GfaPathLength::from_usize(0)),
+        predecessor: None,
+        seed_index: 0,
+    }];
+    let source_cost = graph
+        .node_length(source.node())
+        .checked_sub(source.offset().into_length())?;
+
+    Ok(search_target(
+        graph,
+        &mut labels,
+        (0, source_cost),
+        (target.node(), target_threshold),
+        &saturation_thresholds,
+    )?
+    .map(|(label_index, cost)| {
+        GfaPath::from_walk(
+            backtrack_walk(&labels, label_index),
+            source.offset(),
+            cost - target_threshold,
+            graph,
+        )
+    }))
+}
+
+/// Continue the walk ending at the seed label with Dijkstra's algorithm until it reaches the target node with at least the target threshold.
+///
+/// The seed is given as its label index and its cost, and new labels are appended to `labels`.
+/// Returns the label that reaches the target together with its cost.
+pub(crate) fn search_target<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    labels: &mut Vec<Label<IndexType, LengthType>>,
+    (seed_label, seed_cost): (usize, GfaPathLength<LengthType>),
+    (target_node, target_threshold): (DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>),
+    saturation_thresholds: &SaturationThresholds<IndexType, LengthType>,
+) -> Result<Option<(usize, GfaPathLength<LengthType>)>, LengthOverflowError> {
+    let mut open_list = BinaryHeap::new_min();
+    let mut closed_list = HashSet::new();
+    open_list.push((seed_cost, seed_label));
+
+    while let Some((cost, label_index)) = open_list.pop() {
+        let node = labels[label_index].entry.node();
+        if !closed_list.insert(search_state(saturation_thresholds, node, cost)) {
+            continue;
+        }
+
+        if node == target_node && cost >= target_threshold {
+            return Ok(Some((label_index, cost)));
+        }
+
+        // Expand label.
+        for successor in graph.iter_successors(node) {
+            let successor_cost = successor.extend_cost(cost, graph)?;
+            let successor_state =
+                search_state(saturation_thresholds, successor.node(), successor_cost);
+            if successor_state.unsaturated_cost.is_none() && closed_list.contains(&successor_state)
+            {
+                continue;
+            }
+
+            open_list.push((successor_cost, labels.len()));
+            labels.push(Label {
+                entry: successor,
+                predecessor: Some(label_index),
+                seed_index: 0,
+            });
+        }
+    }
+
+    // Terminated without finding the target.
+    Ok(None)
 }
 
-struct ClosedNode<IndexType> {
-    cost: GfaPathLength<IndexType>,
-    predecessor: OptionalDirectedNodeIndex<IndexType>,
+/// Find the shortest path between two locations regardless of their orientation and order.
+///
+/// This computes the minimum over both orientations of both locations and both directions in a single search.
+/// Since the path from `a` to `b` is the reverse complement of the path from the inverted `b` to the inverted `a`,
+/// a search from both orientations of the source to both orientations of the target covers all combinations.
+/// If the graph has asymmetric overlaps, a path and its reverse complement differ in length,
+/// and the combinations are compared by the length of the path from the source side.
+pub fn strand_agnostic_shortest_path<
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: GfaLocation<IndexType, LengthType>,
+    target: GfaLocation<IndexType, LengthType>,
+) -> Result<Option<StrandAgnosticPath<IndexType, LengthType>>, LengthOverflowError> {
+    let sources = [source.into(), source.invert(graph).into()];
+    let targets = [target.into(), target.invert(graph).into()];
+
+    let Some(result) = multi_shortest_path(graph, &sources, &targets)? else {
+        return Ok(None);
+    };
+    Ok(Some(match (result.source_index, result.target_index) {
+        (0, 0) => StrandAgnosticPath {
+            path: result.path(graph),
+            combination: StrandCombination::SourceToTarget,
+        },
+        (0, 1) => StrandAgnosticPath {
+            path: result.path(graph),
+            combination: StrandCombination::SourceToInvertedTarget,
+        },
+        (1, 0) => StrandAgnosticPath {
+            path: result.path(graph),
+            combination: StrandCombination::InvertedSourceToTarget,
+        },
+        (1, 1) => StrandAgnosticPath {
+            path: result.inverted_path(graph)?,
+            combination: StrandCombination::TargetToSource,
+        },
+        _ => unreachable!(),
+    }))
 }
 
-pub fn shortest_path<IndexType: GraphIndexInteger, NodeData: GfaNodeData, EdgeData: GfaEdgeData>(
-    graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
-    source: GfaLocation<IndexType>,
-    target: GfaLocation<IndexType>,
-) -> Option<GfaPath<IndexType>> {
-    // We search in reverse such that we don't need to invert the path after backtracking.
-    let (source, target) = (target.invert(graph), source.invert(graph));
+/// Find the shortest path between two locations, identifying each with its equivalent locations.
+///
+/// Locations inside overlaps have equivalent locations on the other side of the overlap, see [`GfaLocation::equivalent_locations`].
+/// The search runs from all locations equivalent to `source` to all locations equivalent to `target`,
+/// so equivalent locations have distance zero, and the distance does not depend on the representatives that are used.
+pub fn equivalence_aware_shortest_path<
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: GfaLocation<IndexType, LengthType>,
+    target: GfaLocation<IndexType, LengthType>,
+) -> Result<Option<GfaPath<IndexType, LengthType>>, LengthOverflowError> {
+    let sources = source
+        .equivalent_locations(graph)
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<_>>();
+    let targets = target
+        .equivalent_locations(graph)
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<_>>();
+
+    Ok(multi_shortest_path(graph, &sources, &targets)?.map(|result| result.path(graph)))
+}
+
+/// Find the shortest path from any location in the source ranges to any location in the target ranges.
+///
+/// Ranges may lie on multiple nodes, for example to represent an alignment that spans several segments.
+/// Returns `None` if no target range is reachable from any source range.
+pub fn set_to_set_shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    sources: &[GfaLocationRange<IndexType, LengthType>],
+    targets: &[GfaLocationRange<IndexType, LengthType>],
+) -> Result<Option<SetToSetPath<IndexType, LengthType>>, LengthOverflowError> {
+    Ok(
+        multi_shortest_path(graph, sources, targets)?.map(|result| SetToSetPath {
+            path: result.path(graph),
+            source_index: result.source_index,
+            target_index: result.target_index,
+        }),
+    )
+}
+
+/// Find the shortest path from any of the sources to any of the targets.
+///
+/// Returns the indices of the source and target that are connected by the path.
+pub(crate) fn multi_shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    sources: &[GfaLocationRange<IndexType, LengthType>],
+    targets: &[GfaLocationRange<IndexType, LengthType>],
+) -> Result<Option<MultiShortestPath<IndexType, LengthType>>, LengthOverflowError> {
+    let targets = SearchTargets::new(graph, targets.to_vec())?;
+    Ok(multi_shortest_paths(graph, sources, &targets, 1, None)?
+        .into_iter()
+        .next())
+}
+
+/// Find the shortest paths from any of the sources to each of the `max_target_count` closest targets.
+///
+/// All sources are seeded into the priority queue at their offsets, and the search terminates once no further target can be reached with a shorter path.
+/// Targets further away than `max_distance` are ignored.
+/// The paths are returned in order of increasing length, with at most one path per target.
+pub(crate) fn multi_shortest_paths<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    sources: &[GfaLocationRange<IndexType, LengthType>],
+    targets: &SearchTargets<IndexType, LengthType>,
+    max_target_count: usize,
+    max_distance: Option<GfaPathLength<LengthType>>,
+) -> Result<Vec<MultiShortestPath<IndexType, LengthType>>, LengthOverflowError> {
+    let SearchTargets {
+        targets,
+        target_thresholds,
+        max_upper_thresholds,
+        entry_slack,
+        saturation_thresholds,
+    } = targets;
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Seed the sources at the end of their ranges, since that is where paths leaving the node start.
+    // Target ranges on the same node that end before the end of a source range are reached by an additional seed.
+    let mut seeds = Vec::new();
+    for (source_index, source) in sources.iter().enumerate() {
+        seeds.push((source_index, source.limit()));
+        for target in targets {
+            if target.node() == source.node()
+                && target.limit() < source.limit()
+                && target.limit() >= source.offset()
+            {
+                seeds.push((source_index, source.offset().max(target.offset())));
+            }
+        }
+    }
+
+    // Labels at target nodes that are not yet expanded, with the minimum distance at which they may reach a target.
+    // This distance does not grow with the cost of the label, so they are kept in a separate priority queue.
+    let zero = GfaPathLength::from_usize(0);
+    let target_distance = |node, cost: GfaPathLength<LengthType>| {
+        max_upper_thresholds
+            .get(&node)
+            .map(|threshold| cost.checked_sub(*threshold).unwrap_or(zero))
+    };
+    let mut target_labels = BinaryHeap::new_min();
+    let mut is_expanded = Vec::new();
 
+    let mut labels = Vec::new();
     let mut open_list = BinaryHeap::new_min();
-    let mut closed_list = HashMap::<DirectedNodeIndex<IndexType>, ClosedNode<IndexType>>::new();
-    open_list.push(OpenNode::new_root(source.node()));
-
-    while let Some(open_node) = open_list.pop() {
-        // Close node.
-        closed_list.insert(
-            open_node.node,
-            ClosedNode {
-                cost: open_node.cost,
-                predecessor: open_node.predecessor,
-            },
+    let mut closed_list = HashSet::new();
+    for (seed_index, (source_index, offset)) in seeds.iter().copied().enumerate() {
+        let node = sources[source_index].node();
+        let cost = graph.node_length(node).checked_sub(offset.into_length())?;
+        if let Some(distance) = target_distance(node, cost) {
+            target_labels.push((distance, labels.len()));
+        }
+        open_list.push((cost, labels.len()));
+        labels.push(Label {
+            entry: Successor::new(node, zero),
+            predecessor: None,
+            seed_index,
+        });
+        is_expanded.push(false);
+    }
+
+    // Candidates are found out of order, so they are kept in a priority queue until no shorter path to any target can be found anymore.
+    let mut candidates = BinaryHeap::new_min();
+    let mut finished_targets = HashSet::new();
+    let mut results = Vec::new();
+
+    while let Some((cost, label_index)) = open_list.pop() {
+        // Any target found from now on is found by a label at a target node that is not yet expanded, including this label,
+        // or by a label that is created later from a label that costs at least as much as this one.
+        while let Some((_, target_label)) = target_labels.peek()
+            && is_expanded[*target_label]
+        {
+            target_labels.pop();
+        }
+        let distance_bound = target_labels
+            .peek()
+            .map(|(distance, _)| *distance)
+            .into_iter()
+            .chain([cost.checked_sub(*entry_slack).unwrap_or(zero)])
+            .min()
+            .unwrap();
+        is_expanded[label_index] = true;
+        if max_distance.is_some_and(|max_distance| distance_bound > max_distance) {
+            break;
+        }
+
+        finish_candidates(
+            &mut candidates,
+            &mut finished_targets,
+            &mut results,
+            Some(distance_bound),
         );
+        if finished_targets.len() >= max_target_count {
+            break;
+        }
+
+        let node = labels[label_index].entry.node();
+        if !closed_list.insert(search_state(saturation_thresholds, node, cost)) {
+            continue;
+        }
+
+        for thresholds in target_thresholds.get(&node).into_iter().flatten() {
+            if cost >= thresholds.lower && !finished_targets.contains(&thresholds.target_index) {
+                let distance = cost.checked_sub(thresholds.upper).unwrap_or(zero);
+                candidates.push((distance, thresholds.target_index, label_index, cost));
+            }
+        }
 
-        if open_node.node == target.node() {
-            // Target found, backtrack path and compute actual cost.
-            let cost =
-                open_node.cost + target.offset().into_length() - source.offset().into_length();
-
-            // Initialise path with target node.
-            let mut path = vec![PathElement::new_inverted(
-                target.node(),
-                GfaNodeOffset::from_usize(0),
-                target.offset(),
-                graph,
-            )];
-
-            // Collect nodes.
-            // While backtracking, always assume that nodes start at offset 0.
-            let mut current_node = open_node.node;
-            while let Some(predecessor) = closed_list
-                .get(&current_node)
-                .unwrap()
-                .predecessor
-                .into_option()
+        // Expand label.
+        for successor in graph.iter_successors(node) {
+            let successor_cost = successor.extend_cost(cost, graph)?;
+            let successor_state =
+                search_state(saturation_thresholds, successor.node(), successor_cost);
+            if successor_state.unsaturated_cost.is_none() && closed_list.contains(&successor_state)
             {
-                let offset = GfaNodeOffset::from_usize(0);
-                let limit = closed_list.get(&current_node).unwrap().cost
-                    - closed_list.get(&predecessor).unwrap().cost;
-                path.push(PathElement::new_inverted(
-                    predecessor,
-                    offset,
-                    limit.into_offset(),
-                    graph,
-                ));
-                current_node = predecessor;
+                continue;
             }
 
-            // Adjust offset from source node.
-            let mut remaining_offset = source.offset();
-            for path_element in path.iter_mut().rev() {
-                remaining_offset = path_element.decrease_limit(remaining_offset);
-                if remaining_offset.into_raw().is_zero() {
-                    break;
-                }
+            if let Some(distance) = target_distance(successor.node(), successor_cost) {
+                target_labels.push((distance, labels.len()));
             }
-            assert!(
-                remaining_offset.into_raw().is_zero(),
-                "Found a path of negative length.",
-            );
-
-            // Return path.
-            return Some(GfaPath::new(path, cost));
-        }
-
-        // Expand node.
-        for outgoing_edge in graph.iter_outgoing_edges(open_node.node) {
-            let node = outgoing_edge.to();
-            let cost = open_node.cost + graph.node_data(open_node.node.into_bidirected()).len()
-                - GfaPathLength::from_usize(
-                    graph
-                        .directed_edge_data(outgoing_edge.index())
-                        .data()
-                        .overlap()
-                        .into(),
-                );
-            let predecessor = open_node.node.into();
-
-            if let Some(closed_node) = closed_list.get(&node) {
-                assert!(cost >= closed_node.cost);
-            } else {
-                open_list.push(OpenNode {
-                    node,
-                    cost,
-                    predecessor,
-                });
+            open_list.push((successor_cost, labels.len()));
+            labels.push(Label {
+                entry: successor,
+                predecessor: Some(label_index),
+                seed_index: labels[label_index].seed_index,
+            });
+            is_expanded.push(false);
+        }
+    }
+
+    finish_candidates(&mut candidates, &mut finished_targets, &mut results, None);
+
+    Ok(results
+        .into_iter()
+        .take(max_target_count)
+        .filter(|(distance, _, _, _)| {
+            max_distance.is_none_or(|max_distance| *distance <= max_distance)
+        })
+        .map(|(distance, target_index, label_index, cost)| {
+            let walk = backtrack_walk(&labels, label_index);
+            let (source_index, source_offset) = seeds[labels[label_index].seed_index];
+            let target_node = targets[target_index].node();
+            MultiShortestPath {
+                source_index,
+                target_index,
+                walk,
+                source_offset,
+                target_offset: (graph.node_length(target_node) - (cost - distance)).into_offset(),
+                length: distance,
+            }
+        })
+        .collect())
+}
+
+/// Compute the cost below which labels at each node must not be merged with cheaper labels.
+///
+/// A label reaches a target if its cost is at least the threshold of the target.
+/// A continuation from a node may only fail to reach a target if the target lies inside the overlaps that the continuation enters,
+/// i.e. if the continuation adds less than the threshold of the target.
+/// The saturation threshold of a node is the maximum difference between the threshold of a target and the cost of a continuation to it.
+/// Labels at or above the saturation threshold of their node reach every target that a more expensive label reaches,
+/// so they are merged as in Dijkstra's algorithm.
+///
+/// The thresholds are propagated backwards from the targets, and only nodes with a positive saturation threshold are returned.
+/// Since a continuation adds the length of each node outside of its overlaps, these are the nodes near targets that lie inside overlaps.
+pub(crate) fn saturation_thresholds<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    target_thresholds: impl IntoIterator<
+        Item = (DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>),
+    >,
+) -> Result<SaturationThresholds<IndexType, LengthType>, LengthOverflowError> {
+    let mut saturation_thresholds = HashMap::new();
+    let mut open_list = BinaryHeap::new();
+    open_list.extend(
+        target_thresholds
+            .into_iter()
+            .map(|(node, threshold)| (threshold, node)),
+    );
+
+    while let Some((threshold, node)) = open_list.pop() {
+        if threshold.into_raw().is_zero() || saturation_thresholds.contains_key(&node) {
+            continue;
+        }
+        saturation_thresholds.insert(node, threshold);
+
+        // The inverse of the edges leaving the inverted node enter the node, and their tail overlaps are the head overlaps of the entering edges.
+        let node_length = graph.node_length(node);
+        for inverse in graph.iter_successors(graph.invert(node)) {
+            let predecessor = graph.invert(inverse.node());
+            let available = threshold.checked_add(inverse.tail_overlap())?;
+            let weight = inverse
+                .gap()
+                .unwrap_or(GfaPathLength::from_usize(0))
+                .checked_add(node_length)?;
+            if available > weight && !saturation_thresholds.contains_key(&predecessor) {
+                open_list.push((available - weight, predecessor));
             }
         }
     }
 
-    // Terminated without finding the target.
-    None
+    Ok(saturation_thresholds)
+}
+
+pub(crate) fn search_state<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    saturation_thresholds: &SaturationThresholds<IndexType, LengthType>,
+    node: DirectedNodeIndex<IndexType>,
+    cost: GfaPathLength<LengthType>,
+) -> SearchState<IndexType, LengthType> {
+    let is_unsaturated = saturation_thresholds
+        .get(&node)
+        .is_some_and(|threshold| cost < *threshold);
+    SearchState {
+        node,
+        unsaturated_cost: is_unsaturated.then_some(cost),
+    }
+}
+
+/// Collect the entries of the labels from the root to the given label.
+pub(crate) fn backtrack_walk<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    labels: &[Label<IndexType, LengthType>],
+    label_index: usize,
+) -> Vec<Successor<IndexType, LengthType>> {
+    let mut walk = Vec::new();
+    let mut current = Some(label_index);
+    while let Some(label_index) = current {
+        let label = &labels[label_index];
+        walk.push(label.entry);
+        current = label.predecessor;
+    }
+    walk.reverse();
+    walk
+}
+
+/// Move all candidates with a distance of at most `distance_bound` into the results, at most one per target.
+fn finish_candidates<LengthType: GraphIndexInteger>(
+    candidates: &mut BinaryHeap<Candidate<LengthType>, MinComparator>,
+    finished_targets: &mut HashSet<usize>,
+    results: &mut Vec<Candidate<LengthType>>,
+    distance_bound: Option<GfaPathLength<LengthType>>,
+) {
+    while let Some(candidate) = candidates.peek().copied() {
+        if distance_bound.is_some_and(|distance_bound| candidate.0 > distance_bound) {
+            break;
+        }
+
+        candidates.pop();
+        if finished_targets.insert(candidate.1) {
+            results.push(candidate);
+        }
+    }
 }
 
-impl<IndexType: GraphIndexInteger> OpenNode<IndexType> {
-    fn new_root(node: DirectedNodeIndex<IndexType>) -> Self {
-        Self {
-            node,
-            cost: GfaPathLength::from_usize(0),
-            predecessor: OptionalDirectedNodeIndex::new_none(),
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    SearchTargets<IndexType, LengthType>
+{
+    /// Derive the thresholds of the targets.
+    ///
+    /// Returns an error if a threshold does not fit into the length type.
+    pub(crate) fn new(
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        targets: Vec<GfaLocationRange<IndexType, LengthType>>,
+    ) -> Result<Self, LengthOverflowError> {
+        // Costs are measured from the source to the end of the node of a label.
+        // A label at a target node reaches the target range if its cost is at least the lower threshold,
+        // and it reaches the start of the target range if its cost is at least the upper threshold.
+        let mut target_thresholds = HashMap::<_, Vec<_>>::new();
+        for (target_index, target) in targets.iter().enumerate() {
+            let node_length = graph.node_length(target.node());
+            target_thresholds
+                .entry(target.node())
+                .or_default()
+                .push(TargetThresholds {
+                    target_index,
+                    lower: node_length.checked_sub(target.limit().into_length())?,
+                    upper: node_length.checked_sub(target.offset().into_length())?,
+                });
+        }
+        let max_upper_thresholds = target_thresholds
+            .iter()
+            .map(|(node, thresholds)| {
+                let upper = thresholds.iter().map(|thresholds| thresholds.upper);
+                (*node, upper.max().unwrap())
+            })
+            .collect();
+        let mut entry_slack = GfaPathLength::from_usize(0);
+        for target in &targets {
+            for inverse in graph.iter_successors(graph.invert(target.node())) {
+                let offset = target.offset().into_length();
+                if inverse.tail_overlap() > offset {
+                    entry_slack = entry_slack.max(inverse.tail_overlap() - offset);
+                }
+            }
         }
+        let saturation_thresholds = saturation_thresholds(
+            graph,
+            target_thresholds.iter().map(|(node, thresholds)| {
+                let lower = thresholds.iter().map(|thresholds| thresholds.lower);
+                (*node, lower.max().unwrap())
+            }),
+        )?;
+
+        Ok(Self {
+            targets,
+            target_thresholds,
+            max_upper_thresholds,
+            entry_slack,
+            saturation_thresholds,
+        })
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    MultiShortestPath<IndexType, LengthType>
+{
+    pub(crate) fn path(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> GfaPath<IndexType, LengthType> {
+        GfaPath::from_walk(
+            self.walk.iter().copied(),
+            self.source_offset,
+            self.length,
+            graph,
+        )
+    }
+
+    /// Returns the reverse complement of the path.
+    pub(crate) fn inverted_path(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> Result<GfaPath<IndexType, LengthType>, LengthOverflowError> {
+        // The edge entering an inverted node is the inverse of the edge that left the original node.
+        Ok(self.reverse_complement(
+            graph,
+            |entry, node| entry.inverse(node),
+            self.inverted_length()?,
+        ))
+    }
+
+    /// Returns the reverse complement of a path that was found in the [`SwappedOverlaps`](crate::graph::SwappedOverlaps) view of the graph.
+    ///
+    /// The edges of the view are the inverses of the edges of the reverse complement in the graph,
+    /// and the length of the path in the view is the length of the reverse complement in the graph.
+    pub(crate) fn swapped_inverted_path(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> GfaPath<IndexType, LengthType> {
+        self.reverse_complement(graph, |entry, node| entry.with_node(node), self.length)
+    }
+
+    /// Build the reverse complement of the path, with the edge entering each inverted node given by `inverse_entry`.
+    fn reverse_complement(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        inverse_entry: impl Fn(
+            &Successor<IndexType, LengthType>,
+            DirectedNodeIndex<IndexType>,
+        ) -> Successor<IndexType, LengthType>,
+        length: GfaPathLength<LengthType>,
+    ) -> GfaPath<IndexType, LengthType> {
+        let target_node = self.walk.last().unwrap().node();
+        let inverted_walk = [Successor::new(
+            graph.invert(target_node),
+            GfaPathLength::from_usize(0),
+        )]
+        .into_iter()
+        .chain(
+            self.walk
+                .windows(2)
+                .rev()
+                .map(|window| inverse_entry(&window[1], graph.invert(window[0].node()))),
+        );
+        let inverted_source_offset = graph.node_length(target_node) - self.target_offset;
+
+        GfaPath::from_walk(inverted_walk, inverted_source_offset, length, graph)
+    }
+
+    /// Returns the length of the reverse complement of the path.
+    ///
+    /// This differs from the length of the path if it uses edges with asymmetric overlaps,
+    /// since the reverse complement skips the tail instead of the head overlap of each edge.
+    /// All head overlaps are added before the tail overlaps are subtracted, so intermediate lengths are never negative.
+    /// Returns an error if the length does not fit into the length type,
+    /// or if it would be negative, which requires tail overlaps that exceed the lengths of the nodes they leave.
+    pub(crate) fn inverted_length(&self) -> Result<GfaPathLength<LengthType>, LengthOverflowError> {
+        let length = self
+            .walk
+            .iter()
+            .skip(1)
+            .try_fold(self.length, |length, entry| {
+                length.checked_add(entry.head_overlap())
+            })?;
+        self.walk.iter().skip(1).try_fold(length, |length, entry| {
+            length.checked_sub(entry.tail_overlap())
+        })
+    }
+
+    pub(crate) fn length(&self) -> GfaPathLength<LengthType> {
+        self.length
     }
 }
 
-impl<IndexType: GraphIndexInteger> Ord for OpenNode<IndexType> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.cost
-            .cmp(&other.cost)
-            .then_with(|| self.node.cmp(&other.node))
-            .then_with(|| self.predecessor.cmp(&other.predecessor))
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    StrandAgnosticPath<IndexType, LengthType>
+{
+    pub fn path(&self) -> &GfaPath<IndexType, LengthType> {
+        &self.path
+    }
+
+    pub fn into_path(self) -> GfaPath<IndexType, LengthType> {
+        self.path
+    }
+
+    pub fn combination(&self) -> StrandCombination {
+        self.combination
     }
 }
 
-impl<IndexType: GraphIndexInteger> PartialOrd for OpenNode<IndexType> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    SetToSetPath<IndexType, LengthType>
+{
+    pub fn path(&self) -> &GfaPath<IndexType, LengthType> {
+        &self.path
+    }
+
+    pub fn into_path(self) -> GfaPath<IndexType, LengthType> {
+        self.path
+    }
+
+    /// The index of the source range in which the path starts.
+    pub fn source_index(&self) -> usize {
+        self.source_index
+    }
+
+    /// The index of the target range in which the path ends.
+    pub fn target_index(&self) -> usize {
+        self.target_index
     }
 }
@@ -1,13 +1,17 @@
 use bidirected_adjacency_array::{
     graph::{BidirectedAdjacencyArray, BidirectedEdge},
     index::DirectedNodeIndex,
-    io::gfa1::PlainGfaNodeData,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
 };
 
 use crate::{
-    dijkstra::shortest_path,
-    location::GfaLocation,
-    path::{GfaPath, PathElement},
+    dijkstra::{
+        StrandCombination, multi_shortest_path, set_to_set_shortest_path, shortest_path,
+        strand_agnostic_shortest_path,
+    },
+    io::{LengthMismatchPolicy, UnknownOverlapPolicy, read_gfa1_lengths},
+    location::{GfaLocation, GfaLocationRange},
+    path::{GfaPath, LengthOverflowError, PathElement},
 };
 
 #[test]
@@ -35,6 +39,7 @@ fn test_simple_tight_source() {
         GfaLocation::new(0.into(), 3.into()),
         GfaLocation::new(8.into(), 2.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![
@@ -78,6 +83,7 @@ fn test_simple_tight_target() {
         GfaLocation::new(0.into(), 2.into()),
         GfaLocation::new(8.into(), 0.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![
@@ -121,6 +127,7 @@ fn test_simple_tight_target_iteratively() {
         GfaLocation::new(0.into(), 3.into()),
         GfaLocation::new(8.into(), 0.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![
@@ -161,6 +168,7 @@ fn test_cycle() {
         GfaLocation::new(0.into(), 3.into()),
         GfaLocation::new(0.into(), 0.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![
@@ -201,6 +209,7 @@ fn test_negative_cycle() {
         GfaLocation::new(0.into(), 3.into()),
         GfaLocation::new(0.into(), 0.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![
@@ -240,6 +249,7 @@ fn test_self_loop_internal() {
         GfaLocation::new(0.into(), 1.into()),
         GfaLocation::new(0.into(), 3.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![PathElement::new(0.into(), 1.into(), 3.into())];
@@ -271,6 +281,7 @@ fn test_self_loop_external_one() {
         GfaLocation::new(0.into(), 4.into()),
         GfaLocation::new(0.into(), 2.into()),
     )
+    .unwrap()
     .unwrap();
 
     let expected_path = vec![
@@ -305,12 +316,14 @@ fn test_self_loop_external_two() {
         GfaLocation::new(0.into(), 4.into()),
         GfaLocation::new(0.into(), 1.into()),
     )
+    .unwrap()
     .unwrap();
 
+    // The self loop maps offset 5 to offset 3 and offset 3 to offset 1 of the next copy of A.
     let expected_path = vec![
-        PathElement::new(0.into(), 4.into(), 4.into()),
-        PathElement::new(0.into(), 2.into(), 2.into()),
-        PathElement::new(0.into(), 1.into(), 2.into()),
+        PathElement::new(0.into(), 4.into(), 5.into()),
+        PathElement::new(0.into(), 3.into(), 3.into()),
+        PathElement::new(0.into(), 1.into(), 1.into()),
     ];
     let expected_path = GfaPath::new(expected_path, 1.into());
 
@@ -321,3 +334,232 @@ fn test_self_loop_external_two() {
         "Paths differ:\nExpected: {expected_path:?}\nActual:   {path:?}",
     );
 }
+
+#[test]
+fn test_strand_agnostic_target_to_source() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+    ];
+    let edges = [(0, 2, 1)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let path = strand_agnostic_shortest_path(
+        &graph,
+        GfaLocation::new(2.into(), 1.into()),
+        GfaLocation::new(0.into(), 1.into()),
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![
+        PathElement::new(0.into(), 1.into(), 3.into()),
+        PathElement::new(2.into(), 1.into(), 1.into()),
+    ];
+    let expected_path = GfaPath::new(expected_path, 2.into());
+
+    assert_eq!(path.combination(), StrandCombination::TargetToSource);
+    assert_eq!(path.path().length(), expected_path.length());
+    assert_eq!(
+        path.path().iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {:?}",
+        path.path(),
+    );
+}
+
+#[test]
+fn test_strand_agnostic_inverted_target() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+    ];
+    let edges = [(0, 2, 1)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let path = strand_agnostic_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 1.into()),
+        GfaLocation::new(3.into(), 1.into()),
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![
+        PathElement::new(0.into(), 1.into(), 3.into()),
+        PathElement::new(2.into(), 1.into(), 2.into()),
+    ];
+    let expected_path = GfaPath::new(expected_path, 3.into());
+
+    assert_eq!(
+        path.combination(),
+        StrandCombination::SourceToInvertedTarget
+    );
+    assert_eq!(path.path().length(), expected_path.length());
+    assert_eq!(
+        path.path().iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {:?}",
+        path.path(),
+    );
+}
+
+#[test]
+fn test_set_to_set_spanning_ranges() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+        PlainGfaNodeData::new("C", "CCC"),
+        PlainGfaNodeData::new("D", "DDDDD"),
+    ];
+    let edges = [(0, 2, 0), (2, 4, 0), (4, 6, 0)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let path = set_to_set_shortest_path(
+        &graph,
+        &[
+            GfaLocationRange::new(0.into(), 0.into(), 2.into()),
+            GfaLocationRange::new(2.into(), 0.into(), 1.into()),
+        ],
+        &[GfaLocationRange::new(6.into(), 2.into(), 4.into())],
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![
+        PathElement::new(2.into(), 1.into(), 3.into()),
+        PathElement::new(4.into(), 0.into(), 3.into()),
+        PathElement::new(6.into(), 0.into(), 2.into()),
+    ];
+    let expected_path = GfaPath::new(expected_path, 7.into());
+
+    assert_eq!(path.source_index(), 1);
+    assert_eq!(path.target_index(), 0);
+    assert_eq!(path.path().length(), expected_path.length());
+    assert_eq!(
+        path.path().iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {:?}",
+        path.path(),
+    );
+}
+
+#[test]
+fn test_set_to_set_intersecting_ranges() {
+    let nodes = vec![PlainGfaNodeData::new("A", "AAAAA")];
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, PlainGfaEdgeData>::new(nodes.into(), Default::default());
+
+    let path = set_to_set_shortest_path(
+        &graph,
+        &[GfaLocationRange::new(0.into(), 1.into(), 3.into())],
+        &[GfaLocationRange::new(0.into(), 0.into(), 2.into())],
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![PathElement::new(0.into(), 1.into(), 1.into())];
+    let expected_path = GfaPath::new(expected_path, 0.into());
+
+    assert_eq!(path.path().length(), expected_path.length());
+    assert_eq!(
+        path.path().iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {:?}",
+        path.path(),
+    );
+
+    assert!(
+        set_to_set_shortest_path(
+            &graph,
+            &[GfaLocationRange::new(0.into(), 3.into(), 4.into())],
+            &[GfaLocationRange::new(0.into(), 0.into(), 2.into())],
+        )
+        .unwrap()
+        .is_none()
+    );
+}
+
+#[test]
+fn test_path_longer_than_index_type() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "A".repeat(200)),
+        PlainGfaNodeData::new("B", "C".repeat(300)),
+        PlainGfaNodeData::new("C", "G".repeat(200)),
+    ];
+    let edges = [(0, 2, 0), (2, 4, 0)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let path = shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 50.into()),
+        GfaLocation::new(4.into(), 150.into()),
+    )
+    .unwrap()
+    .unwrap();
+
+    let expected_path = vec![
+        PathElement::new(0.into(), 50.into(), 200.into()),
+        PathElement::new(2.into(), 0.into(), 300.into()),
+        PathElement::new(4.into(), 0.into(), 150.into()),
+    ];
+    let expected_path = GfaPath::new(expected_path, 600.into());
+
+    assert_eq!(path.length(), expected_path.length());
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        expected_path.iter().collect::<Vec<_>>(),
+        "Paths differ:\nExpected: {expected_path:?}\nActual:   {path:?}",
+    );
+}
+
+#[test]
+fn test_inverted_length_with_overlong_tail_overlap() {
+    // The overlap consumes three bases of A, which only has two.
+    let gfa = "S\tA\t*\tLN:i:2\nS\tB\t*\tLN:i:5\nL\tA\t+\tB\t+\t3D\n";
+    let graph = read_gfa1_lengths::<u8>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    let result = multi_shortest_path(
+        &graph,
+        &[GfaLocation::new(0.into(), 2.into()).into()],
+        &[GfaLocation::new(2.into(), 0.into()).into()],
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(result.length().into_usize(), 0);
+    // The reverse complement would have a negative length.
+    assert_eq!(result.inverted_length(), Err(LengthOverflowError));
+}
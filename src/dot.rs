@@ -0,0 +1,170 @@
+use std::{collections::HashSet, fmt::Display, io::Write};
+
+use bidirected_adjacency_array::index::GraphIndexInteger;
+use spqr_tree::{
+    decomposition::{
+        SPQRNodeType,
+        indices::{SPQREdgeIndex, SPQRNodeIndex},
+    },
+    graph::{NamedNodeData, StaticGraph},
+};
+
+use crate::{
+    decomposition::DecompositionLookup,
+    path::{GfaPath, PathItem},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Write the SPQR trees of all blocks of the decomposition in the [DOT format](https://graphviz.org/doc/info/lang.html) of Graphviz.
+///
+/// Each block is drawn as a cluster, and each SPQR edge is labelled with the names of its separation pair.
+/// The SPQR nodes in `highlighted` are filled, for example the nodes returned by [`spqr_nodes_on_path`].
+pub fn write_spqr_tree_dot<IndexType: GraphIndexInteger, NodeData: NamedNodeData, EdgeData>(
+    lookup: &DecompositionLookup<'_, '_, IndexType, NodeData, EdgeData>,
+    highlighted: &HashSet<SPQRNodeIndex<IndexType>>,
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    let decomposition = lookup.decomposition();
+    let graph = decomposition.graph();
+    writeln!(writer, "graph spqr_tree {{")?;
+
+    for block_index in lookup.iter_blocks() {
+        writeln!(writer, "  subgraph cluster_B{block_index} {{")?;
+        writeln!(writer, "    label=\"B{block_index}\";")?;
+
+        for (spqr_node_index, _) in lookup.iter_tree(block_index) {
+            let spqr_node = lookup.spqr_node(spqr_node_index);
+            let name = decomposition.spqr_node_name(spqr_node_index);
+            let nodes = spqr_node
+                .iter_nodes()
+                .map(|node| escape(&graph.node_name(node)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let shape = match spqr_node.spqr_node_type() {
+                SPQRNodeType::SNode => "ellipse",
+                SPQRNodeType::PNode => "diamond",
+                SPQRNodeType::RNode => "box",
+            };
+            let style = if highlighted.contains(&spqr_node_index) {
+                ", style=filled, fillcolor=orange"
+            } else {
+                ""
+            };
+            writeln!(
+                writer,
+                "    \"{name}\" [label=\"{name}\\n{nodes}\", shape={shape}{style}];",
+            )?;
+        }
+
+        for (_, spqr_edge) in decomposition.iter_spqr_edges_in_block(block_index) {
+            let (from, to) = spqr_edge.endpoints();
+            let (u, v) = spqr_edge.virtual_edge();
+            writeln!(
+                writer,
+                "    \"{}\" -- \"{}\" [label=\"{} {}\"];",
+                decomposition.spqr_node_name(from),
+                decomposition.spqr_node_name(to),
+                escape(&graph.node_name(u)),
+                escape(&graph.node_name(v)),
+            )?;
+        }
+
+        writeln!(writer, "  }}")?;
+    }
+
+    writeln!(writer, "}}")
+}
+
+/// Write the skeleton graph of an SPQR node in the DOT format.
+///
+/// Real edges are drawn solid and virtual edges dashed.
+/// Virtual edges are labelled with the value returned by `virtual_edge_length`, if any.
+///
+/// Panics if the SPQR node does not exist.
+pub fn write_skeleton_dot<
+    IndexType: GraphIndexInteger,
+    NodeData: NamedNodeData,
+    EdgeData,
+    Length: Display,
+>(
+    lookup: &DecompositionLookup<'_, '_, IndexType, NodeData, EdgeData>,
+    spqr_node_index: SPQRNodeIndex<IndexType>,
+    virtual_edge_length: impl Fn(SPQREdgeIndex<IndexType>) -> Option<Length>,
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    let decomposition = lookup.decomposition();
+    let graph = decomposition.graph();
+    let spqr_node = lookup.spqr_node(spqr_node_index);
+
+    writeln!(
+        writer,
+        "graph \"{}\" {{",
+        decomposition.spqr_node_name(spqr_node_index),
+    )?;
+
+    for node in spqr_node.iter_nodes() {
+        writeln!(writer, "  \"{}\";", escape(&graph.node_name(node)))?;
+    }
+
+    for edge in spqr_node.iter_edges() {
+        let (u, v) = graph.edge_endpoints(edge);
+        writeln!(
+            writer,
+            "  \"{}\" -- \"{}\";",
+            escape(&graph.node_name(u)),
+            escape(&graph.node_name(v)),
+        )?;
+    }
+
+    for (spqr_edge_index, spqr_edge) in decomposition.iter_spqr_edges_in_block(spqr_node.block()) {
+        let (from, to) = spqr_edge.endpoints();
+        if from != spqr_node_index && to != spqr_node_index {
+            continue;
+        }
+
+        let (u, v) = spqr_edge.virtual_edge();
+        let label = match virtual_edge_length(spqr_edge_index) {
+            Some(length) => format!(", label=\"{length}\""),
+            None => String::new(),
+        };
+        writeln!(
+            writer,
+            "  \"{}\" -- \"{}\" [style=dashed{label}];",
+            escape(&graph.node_name(u)),
+            escape(&graph.node_name(v)),
+        )?;
+    }
+
+    writeln!(writer, "}}")
+}
+
+/// Returns the SPQR nodes whose skeletons contain an edge traversed by the path.
+pub fn spqr_nodes_on_path<IndexType: GraphIndexInteger, NodeData: NamedNodeData, EdgeData>(
+    lookup: &DecompositionLookup<'_, '_, IndexType, NodeData, EdgeData>,
+    path: &GfaPath<IndexType, impl GraphIndexInteger>,
+) -> HashSet<SPQRNodeIndex<IndexType>> {
+    let mut spqr_nodes = HashSet::new();
+    let mut previous_node = None;
+    for item in path.iter_items() {
+        match item {
+            PathItem::Node(element) => {
+                let node = element.node();
+                if let Some(previous_node) = previous_node {
+                    spqr_nodes.extend(lookup.spqr_nodes_of_edge(previous_node, node));
+                }
+                previous_node = Some(node);
+            }
+            // A gap is not an edge of the decomposed graph.
+            PathItem::Gap(_) => previous_node = None,
+        }
+    }
+
+    spqr_nodes
+}
+
+/// Escape a name for use inside a quoted DOT identifier.
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
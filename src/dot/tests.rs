@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+use spqr_tree::{decomposition::indices::SPQRNodeIndex, io::plain_spqr_file::read_plain_spqr};
+
+use crate::{
+    decomposition::DecompositionLookup,
+    dot::{spqr_nodes_on_path, write_skeleton_dot, write_spqr_tree_dot},
+    path::{GfaPath, GfaPathLength, PathElement},
+};
+
+/// The cycle A, B, C, D with the chord A, C.
+fn graph() -> BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> {
+    let nodes = ["A", "B", "C", "D"]
+        .map(|name| PlainGfaNodeData::new(name, "AC"))
+        .to_vec();
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)].map(|(from, to)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(2 * from),
+            DirectedNodeIndex::new(2 * to),
+            0,
+        )
+    });
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+const SPQR: &str = "H v0.1 https://github.com/sebschmi/SPQR-tree-file-format
+G G0 A B C D
+B B0 G0 A B C D
+S S0 B0 A B C
+E E0 S0 B0 A B
+E E1 S0 B0 B C
+S S1 B0 C D A
+E E2 S1 B0 C D
+E E3 S1 B0 D A
+P P2 B0 A C
+E E4 P2 B0 A C
+V V0 S0 P2 A C
+V V1 S1 P2 A C
+";
+
+#[test]
+fn test_spqr_tree_dot() {
+    let graph = graph();
+    let decomposition = read_plain_spqr(&graph, &mut SPQR.as_bytes()).unwrap();
+    let lookup = DecompositionLookup::new(&decomposition);
+
+    let mut buffer = Vec::new();
+    write_spqr_tree_dot(
+        &lookup,
+        &HashSet::from([SPQRNodeIndex::from_usize(2)]),
+        &mut buffer,
+    )
+    .unwrap();
+    let dot = String::from_utf8(buffer).unwrap();
+
+    assert!(dot.starts_with("graph spqr_tree {\n"));
+    assert!(dot.contains("subgraph cluster_B0 {"));
+    assert!(dot.contains("\"S0\" [label=\"S0\\nA B C\", shape=ellipse];"));
+    assert!(
+        dot.contains("\"P2\" [label=\"P2\\nA C\", shape=diamond, style=filled, fillcolor=orange];")
+    );
+    assert!(dot.contains("\"S0\" -- \"P2\" [label=\"A C\"];"));
+    assert!(dot.contains("\"S1\" -- \"P2\" [label=\"A C\"];"));
+}
+
+#[test]
+fn test_skeleton_dot() {
+    let graph = graph();
+    let decomposition = read_plain_spqr(&graph, &mut SPQR.as_bytes()).unwrap();
+    let lookup = DecompositionLookup::new(&decomposition);
+
+    let mut buffer = Vec::new();
+    write_skeleton_dot(
+        &lookup,
+        SPQRNodeIndex::from_usize(0),
+        |spqr_edge| (spqr_edge.into_usize() == 0).then_some(5),
+        &mut buffer,
+    )
+    .unwrap();
+    let dot = String::from_utf8(buffer).unwrap();
+
+    assert!(dot.starts_with("graph \"S0\" {\n"));
+    assert!(dot.contains("  \"A\" -- \"B\";\n"));
+    assert!(dot.contains("  \"B\" -- \"C\";\n"));
+    assert!(dot.contains("  \"A\" -- \"C\" [style=dashed, label=\"5\"];\n"));
+    assert!(!dot.contains("\"D\""));
+}
+
+#[test]
+fn test_spqr_nodes_on_path() {
+    let graph = graph();
+    let decomposition = read_plain_spqr(&graph, &mut SPQR.as_bytes()).unwrap();
+    let lookup = DecompositionLookup::new(&decomposition);
+
+    // The path uses the chord from A to C and then the edge from C to D.
+    let path = GfaPath::<u8, u16>::new(
+        vec![
+            PathElement::new(0.into(), 0.into(), 2.into()),
+            PathElement::new(4.into(), 0.into(), 2.into()),
+            PathElement::new(6.into(), 0.into(), 1.into()),
+        ],
+        GfaPathLength::from_usize(5),
+    );
+    assert_eq!(
+        spqr_nodes_on_path(&lookup, &path),
+        HashSet::from([SPQRNodeIndex::from_usize(1), SPQRNodeIndex::from_usize(2)]),
+    );
+}
@@ -0,0 +1,419 @@
+use std::collections::BTreeSet;
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex};
+
+use crate::{
+    graph::{SequenceGraph, Successor},
+    hub_labels::HubLabels,
+    location::GfaLocation,
+    path::{GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A graph that supports inserting and removing nodes and edges, and changing node lengths.
+///
+/// Removed nodes keep their indices, so indices stay stable across updates, but they have no edges.
+pub struct DynamicGraph<IndexType, LengthType> {
+    node_lengths: Vec<GfaPathLength<LengthType>>,
+    /// The successors of each directed node.
+    successors: Vec<Vec<Successor<IndexType, LengthType>>>,
+    is_removed: Vec<bool>,
+}
+
+/// A distance oracle over a graph that changes, with labels kept per connected component.
+///
+/// The graph is split into its connected components, and each component has its own [`HubLabels`].
+/// Inserting nodes and edges repairs the labels locally: an edge between two components appends the labels of the smaller
+/// component to those of the larger one, and the pruned searches of the hubs in the labels of its ends are resumed over the new edge.
+/// Removing nodes or edges and changing node lengths can make distances longer, which pruned labels cannot repair,
+/// so these updates rebuild the labels of the affected component from scratch.
+/// Repaired labels store their backward labels even if all overlaps are symmetric, see [`HubLabels`].
+/// Overlaps must not exceed the lengths of the nodes they enter.
+pub struct DynamicHubLabels<IndexType, LengthType> {
+    graph: DynamicGraph<IndexType, LengthType>,
+    /// The slot of the component of each bidirected node, or `usize::MAX` for removed nodes.
+    node_components: Vec<usize>,
+    /// The index of each bidirected node within its component.
+    local_indices: Vec<usize>,
+    /// The labelled components, where `None` marks a free slot.
+    components: Vec<Option<LabelledComponent<IndexType, LengthType>>>,
+    /// The free slots of `components`.
+    free_slots: Vec<usize>,
+}
+
+/// A connected component together with the labels over its nodes.
+struct LabelledComponent<IndexType, LengthType> {
+    nodes: Vec<NodeIndex<IndexType>>,
+    labels: HubLabels<IndexType, LengthType>,
+}
+
+/// A connected component of a [`DynamicGraph`] with its nodes numbered from zero.
+struct ComponentView<'graph, IndexType, LengthType> {
+    graph: &'graph DynamicGraph<IndexType, LengthType>,
+    nodes: &'graph [NodeIndex<IndexType>],
+    local_indices: &'graph [usize],
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    DynamicGraph<IndexType, LengthType>
+{
+    /// Copy the nodes and edges of the graph.
+    pub fn from_graph(graph: &impl SequenceGraph<IndexType, LengthType = LengthType>) -> Self {
+        let directed_nodes = (0..2 * graph.node_count()).map(DirectedNodeIndex::from_usize);
+        Self {
+            node_lengths: (0..graph.node_count())
+                .map(|node| graph.node_length(DirectedNodeIndex::from_usize(2 * node)))
+                .collect(),
+            successors: directed_nodes
+                .map(|node| graph.iter_successors(node).collect())
+                .collect(),
+            is_removed: vec![false; graph.node_count()],
+        }
+    }
+
+    /// Add a node without edges and return its index.
+    pub fn add_node(&mut self, length: GfaPathLength<LengthType>) -> NodeIndex<IndexType> {
+        let node = NodeIndex::from_usize(self.node_lengths.len());
+        self.node_lengths.push(length);
+        self.successors.extend([Vec::new(), Vec::new()]);
+        self.is_removed.push(false);
+        node
+    }
+
+    /// Remove the node together with all its edges.
+    pub fn remove_node(&mut self, node: NodeIndex<IndexType>) {
+        for forward in [true, false] {
+            let node = DirectedNodeIndex::from_bidirected(node, forward);
+            for successor in std::mem::take(&mut self.successors[node.into_usize()]) {
+                self.remove_edge(node, successor.node());
+            }
+        }
+        self.is_removed[node.into_usize()] = true;
+    }
+
+    pub fn is_removed(&self, node: NodeIndex<IndexType>) -> bool {
+        self.is_removed[node.into_usize()]
+    }
+
+    pub fn set_node_length(
+        &mut self,
+        node: NodeIndex<IndexType>,
+        length: GfaPathLength<LengthType>,
+    ) {
+        assert!(!self.is_removed(node), "Node {node} is removed");
+        self.node_lengths[node.into_usize()] = length;
+    }
+
+    /// Add an edge from `from` to the successor, together with its inverse.
+    pub fn add_edge(
+        &mut self,
+        from: DirectedNodeIndex<IndexType>,
+        successor: Successor<IndexType, LengthType>,
+    ) {
+        for node in [from, successor.node()] {
+            assert!(
+                !self.is_removed(node.into_bidirected()),
+                "Node {} is removed",
+                node.into_bidirected(),
+            );
+        }
+
+        let inverse_from = successor.node().invert();
+        let inverse = successor.inverse(from.invert());
+        self.successors[from.into_usize()].push(successor);
+        // An edge between the two orientations of a node may be its own inverse.
+        if (inverse_from, inverse) != (from, successor) {
+            self.successors[inverse_from.into_usize()].push(inverse);
+        }
+    }
+
+    /// Remove all edges from `from` to `to`, together with their inverses.
+    ///
+    /// Returns false if there was no such edge.
+    pub fn remove_edge(
+        &mut self,
+        from: DirectedNodeIndex<IndexType>,
+        to: DirectedNodeIndex<IndexType>,
+    ) -> bool {
+        let mut is_removed = false;
+        for (from, to) in [(from, to), (to.invert(), from.invert())] {
+            let successors = &mut self.successors[from.into_usize()];
+            let count = successors.len();
+            successors.retain(|successor| successor.node() != to);
+            is_removed |= successors.len() < count;
+        }
+        is_removed
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> SequenceGraph<IndexType>
+    for DynamicGraph<IndexType, LengthType>
+{
+    type LengthType = LengthType;
+
+    fn node_count(&self) -> usize {
+        self.node_lengths.len()
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.node_lengths[node.into_bidirected().into_usize()]
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        self.successors[node.into_usize()].iter().copied()
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    DynamicHubLabels<IndexType, LengthType>
+{
+    /// Label all connected components of the graph.
+    pub fn new(graph: DynamicGraph<IndexType, LengthType>) -> Self {
+        let node_count = graph.node_count();
+        let mut labels = Self {
+            graph,
+            node_components: vec![usize::MAX; node_count],
+            local_indices: vec![0; node_count],
+            components: Vec::new(),
+            free_slots: Vec::new(),
+        };
+        labels.relabel((0..node_count).map(NodeIndex::from_usize).collect());
+        labels
+    }
+
+    pub fn graph(&self) -> &DynamicGraph<IndexType, LengthType> {
+        &self.graph
+    }
+
+    /// Returns the number of connected components, not counting removed nodes.
+    pub fn component_count(&self) -> usize {
+        self.components.iter().flatten().count()
+    }
+
+    /// Add a node without edges and return its index.
+    pub fn add_node(&mut self, length: GfaPathLength<LengthType>) -> NodeIndex<IndexType> {
+        let node = self.graph.add_node(length);
+        self.node_components.push(usize::MAX);
+        self.local_indices.push(0);
+        self.relabel(BTreeSet::from([node]));
+        node
+    }
+
+    /// Remove the node together with all its edges, and relabel the rest of its component.
+    pub fn remove_node(&mut self, node: NodeIndex<IndexType>) {
+        let mut nodes = self.take_component(node);
+        self.graph.remove_node(node);
+        nodes.remove(&node);
+        self.relabel(nodes);
+    }
+
+    /// Change the length of the node, and relabel its component.
+    pub fn set_node_length(
+        &mut self,
+        node: NodeIndex<IndexType>,
+        length: GfaPathLength<LengthType>,
+    ) {
+        self.graph.set_node_length(node, length);
+        let nodes = self.take_component(node);
+        self.relabel(nodes);
+    }
+
+    /// Add an edge, merge the components of its ends and repair their labels.
+    pub fn add_edge(
+        &mut self,
+        from: DirectedNodeIndex<IndexType>,
+        successor: Successor<IndexType, LengthType>,
+    ) {
+        self.graph.add_edge(from, successor);
+        let slot = self.merge_components(
+            self.node_components[from.into_bidirected().into_usize()],
+            self.node_components[successor.node().into_bidirected().into_usize()],
+        );
+
+        let LabelledComponent { nodes, labels } = self.components[slot].as_mut().unwrap();
+        let view = ComponentView {
+            graph: &self.graph,
+            nodes,
+            local_indices: &self.local_indices,
+        };
+        labels.insert_edge(
+            &view,
+            view.local(from),
+            successor.with_node(view.local(successor.node())),
+        );
+    }
+
+    /// Remove all edges from `from` to `to`, and relabel their component.
+    ///
+    /// Returns false if there was no such edge, in which case nothing is relabelled.
+    pub fn remove_edge(
+        &mut self,
+        from: DirectedNodeIndex<IndexType>,
+        to: DirectedNodeIndex<IndexType>,
+    ) -> bool {
+        if !self.graph.remove_edge(from, to) {
+            return false;
+        }
+        let nodes = self.take_component(from.into_bidirected());
+        self.relabel(nodes);
+        true
+    }
+
+    /// Returns the length of the shortest path from `source` to `target`, or `None` if there is none.
+    ///
+    /// The result is the same as that of [`HubLabels::distance`] on the current graph.
+    pub fn distance(
+        &self,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<Option<GfaPathLength<LengthType>>, LengthOverflowError> {
+        let component = self.node_components[source.node().into_bidirected().into_usize()];
+        if component == usize::MAX
+            || component != self.node_components[target.node().into_bidirected().into_usize()]
+        {
+            return Ok(None);
+        }
+
+        let LabelledComponent { nodes, labels } = self.components[component].as_ref().unwrap();
+        let view = ComponentView {
+            graph: &self.graph,
+            nodes,
+            local_indices: &self.local_indices,
+        };
+        labels.distance(
+            &view,
+            GfaLocation::new(view.local(source.node()), source.offset()),
+            GfaLocation::new(view.local(target.node()), target.offset()),
+        )
+    }
+
+    /// Append the nodes and labels of the smaller component to the larger one, and return the slot of the merged component.
+    fn merge_components(&mut self, first: usize, second: usize) -> usize {
+        if first == second {
+            return first;
+        }
+        let component_size = |slot: usize| self.components[slot].as_ref().unwrap().nodes.len();
+        let (slot, other) = if component_size(first) >= component_size(second) {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        self.free_slots.push(other);
+        let other = self.components[other].take().unwrap();
+        let component = self.components[slot].as_mut().unwrap();
+        for node in &other.nodes {
+            self.node_components[node.into_usize()] = slot;
+            self.local_indices[node.into_usize()] = component.nodes.len();
+            component.nodes.push(*node);
+        }
+        component.labels.append(other.labels);
+        slot
+    }
+
+    /// Free the slot of the component of the node and return its nodes.
+    fn take_component(&mut self, node: NodeIndex<IndexType>) -> BTreeSet<NodeIndex<IndexType>> {
+        let component = self.node_components[node.into_usize()];
+        let Some(LabelledComponent { nodes, .. }) =
+            self.components.get_mut(component).and_then(Option::take)
+        else {
+            return BTreeSet::new();
+        };
+        self.free_slots.push(component);
+
+        for node in &nodes {
+            self.node_components[node.into_usize()] = usize::MAX;
+        }
+        nodes.into_iter().collect()
+    }
+
+    /// Split the nodes into connected components and label each of them.
+    ///
+    /// The nodes must not have edges to nodes outside of the given set.
+    fn relabel(&mut self, mut nodes: BTreeSet<NodeIndex<IndexType>>) {
+        while let Some(root) = nodes.pop_first() {
+            let mut component = vec![root];
+            let mut index = 0;
+            while let Some(node) = component.get(index).copied() {
+                for forward in [true, false] {
+                    for successor in self
+                        .graph
+                        .iter_successors(DirectedNodeIndex::from_bidirected(node, forward))
+                    {
+                        if nodes.remove(&successor.node().into_bidirected()) {
+                            component.push(successor.node().into_bidirected());
+                        }
+                    }
+                }
+                index += 1;
+            }
+            component.sort_unstable();
+
+            let slot = self.free_slots.pop().unwrap_or_else(|| {
+                self.components.push(None);
+                self.components.len() - 1
+            });
+            for (local_index, node) in component.iter().enumerate() {
+                self.node_components[node.into_usize()] = slot;
+                self.local_indices[node.into_usize()] = local_index;
+            }
+            let labels = HubLabels::new(&ComponentView {
+                graph: &self.graph,
+                nodes: &component,
+                local_indices: &self.local_indices,
+            });
+            self.components[slot] = Some(LabelledComponent {
+                nodes: component,
+                labels,
+            });
+        }
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    ComponentView<'_, IndexType, LengthType>
+{
+    /// Map a directed node of the graph to the same orientation of its node in the component.
+    fn local(&self, node: DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType> {
+        DirectedNodeIndex::from_bidirected(
+            NodeIndex::from_usize(self.local_indices[node.into_bidirected().into_usize()]),
+            node.is_forward(),
+        )
+    }
+
+    /// Map a directed node of the component to the same orientation of its node in the graph.
+    fn global(&self, node: DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType> {
+        DirectedNodeIndex::from_bidirected(
+            self.nodes[node.into_bidirected().into_usize()],
+            node.is_forward(),
+        )
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> SequenceGraph<IndexType>
+    for ComponentView<'_, IndexType, LengthType>
+{
+    type LengthType = LengthType;
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.graph.node_length(self.global(node))
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        self.graph
+            .iter_successors(self.global(node))
+            .map(|successor| successor.with_node(self.local(successor.node())))
+    }
+}
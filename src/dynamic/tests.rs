@@ -0,0 +1,156 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, NodeIndex},
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    dynamic::{DynamicGraph, DynamicHubLabels},
+    graph::{SequenceGraph, Successor},
+    hub_labels::HubLabels,
+    location::{GfaLocation, GfaNodeOffset},
+    path::GfaPathLength,
+    test_util::random_graph,
+};
+
+/// Compare the distances between all pairs of locations against labels built from scratch.
+fn assert_valid(labels: &DynamicHubLabels<u16, u64>) {
+    let graph = labels.graph();
+    let rebuilt = HubLabels::new(graph);
+    let locations = (0..2 * graph.node_count())
+        .map(DirectedNodeIndex::<u16>::from_usize)
+        .filter(|node| !graph.is_removed(node.into_bidirected()))
+        .flat_map(|node| {
+            let length = graph.node_length(node).into_usize();
+            [0, length].map(|offset| GfaLocation::new(node, GfaNodeOffset::from_usize(offset)))
+        })
+        .collect::<Vec<_>>();
+
+    for source in &locations {
+        for target in &locations {
+            assert_eq!(
+                labels.distance(*source, *target).unwrap(),
+                rebuilt.distance(graph, *source, *target).unwrap(),
+                "Distances differ from {source:?} to {target:?}",
+            );
+        }
+    }
+}
+
+#[test]
+fn test_random_updates() {
+    for seed in 0..4 {
+        let mut labels = DynamicHubLabels::new(DynamicGraph::from_graph(&random_graph(seed, 8, 8)));
+        assert_valid(&labels);
+
+        let mut state = seed;
+        let mut next = |limit: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % limit
+        };
+
+        for _ in 0..24 {
+            let graph = labels.graph();
+            let nodes = (0..graph.node_count())
+                .map(NodeIndex::<u16>::from_usize)
+                .filter(|node| !graph.is_removed(*node))
+                .collect::<Vec<_>>();
+            let node = nodes[next(nodes.len())];
+            let directed_node = DirectedNodeIndex::from_bidirected(node, next(2) == 0);
+            let length = |node: DirectedNodeIndex<u16>| graph.node_length(node).into_usize();
+
+            match next(6) {
+                0 | 1 => {
+                    let to =
+                        DirectedNodeIndex::from_bidirected(nodes[next(nodes.len())], next(2) == 0);
+                    let overlap = next(length(directed_node).min(length(to)) + 1);
+                    labels.add_edge(
+                        directed_node,
+                        Successor::new(to, GfaPathLength::from_usize(overlap)),
+                    );
+                }
+                2 => {
+                    let to = graph
+                        .iter_successors(directed_node)
+                        .next()
+                        .map(|successor| successor.node());
+                    if let Some(to) = to {
+                        assert!(labels.remove_edge(directed_node, to));
+                    }
+                }
+                3 => {
+                    labels.add_node(GfaPathLength::from_usize(1 + next(8)));
+                }
+                4 => {
+                    if nodes.len() > 1 {
+                        labels.remove_node(node);
+                    }
+                }
+                _ => {
+                    // The new length must not be shorter than the overlaps of the node.
+                    let max_overlap = [directed_node, directed_node.invert()]
+                        .into_iter()
+                        .flat_map(|node| graph.iter_successors(node))
+                        .map(|successor| successor.tail_overlap().into_usize())
+                        .max()
+                        .unwrap_or(0);
+                    labels.set_node_length(
+                        node,
+                        GfaPathLength::from_usize(max_overlap.max(1 + next(8))),
+                    );
+                }
+            }
+            assert_valid(&labels);
+        }
+    }
+}
+
+#[test]
+fn test_merge_and_split() {
+    let nodes = ["A", "B", "C"]
+        .map(|name| PlainGfaNodeData::new(name, "ACGT"))
+        .to_vec();
+    let edges = [BidirectedEdge::new_gfa(
+        DirectedNodeIndex::new(0),
+        DirectedNodeIndex::new(2),
+        1,
+    )];
+    let graph: BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> =
+        BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges));
+    let mut labels = DynamicHubLabels::new(DynamicGraph::from_graph(&graph));
+    assert_eq!(labels.component_count(), 2);
+
+    let source = GfaLocation::new(0.into(), 0.into());
+    let target = GfaLocation::new(4.into(), 4.into());
+    assert_eq!(labels.distance(source, target).unwrap(), None);
+
+    // B to C joins the two components.
+    labels.add_edge(2.into(), Successor::new(4.into(), 2.into()));
+    assert_eq!(labels.component_count(), 1);
+    assert_eq!(
+        labels
+            .distance(source, target)
+            .unwrap()
+            .unwrap()
+            .into_usize(),
+        9
+    );
+
+    // Lengthening B lengthens the path.
+    labels.set_node_length(1.into(), 6.into());
+    assert_eq!(
+        labels
+            .distance(source, target)
+            .unwrap()
+            .unwrap()
+            .into_usize(),
+        11
+    );
+
+    // Removing B splits the component into A and C.
+    labels.remove_node(1.into());
+    assert_eq!(labels.component_count(), 2);
+    assert_eq!(labels.distance(source, target).unwrap(), None);
+}
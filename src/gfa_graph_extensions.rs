@@ -1,17 +1,59 @@
-use bidirected_adjacency_array::{index::GraphIndexInteger, io::gfa1::GfaNodeData};
+use std::borrow::Cow;
+
+use bidirected_adjacency_array::{
+    index::GraphIndexInteger,
+    io::gfa1::{GfaEdgeData, GfaNodeData},
+};
 
 use crate::path::GfaPathLength;
 
-pub trait GfaNodeDataExt<IndexType: GraphIndexInteger> {
-    fn len(&self) -> GfaPathLength<IndexType>;
+pub trait GfaNodeDataExt<LengthType: GraphIndexInteger> {
+    fn len(&self) -> GfaPathLength<LengthType>;
 
     fn is_empty(&self) -> bool {
         self.len().into_usize() == 0
     }
 }
 
-impl<T: GfaNodeData, IndexType: GraphIndexInteger> GfaNodeDataExt<IndexType> for T {
-    fn len(&self) -> GfaPathLength<IndexType> {
+impl<T: GfaNodeData, LengthType: GraphIndexInteger> GfaNodeDataExt<LengthType> for T {
+    fn len(&self) -> GfaPathLength<LengthType> {
         GfaPathLength::from_usize(self.sequence().len())
     }
 }
+
+/// The name of a GFA segment, for node data that may or may not store its sequence.
+pub trait GfaNodeNameExt {
+    fn segment_name(&self) -> Cow<'_, str>;
+}
+
+impl<T: GfaNodeData> GfaNodeNameExt for T {
+    fn segment_name(&self) -> Cow<'_, str> {
+        self.name()
+    }
+}
+
+/// The overlap of an edge, which may consume a different number of bases on each of its endpoints.
+///
+/// The tail overlap is consumed at the end of the node the edge leaves, and the head overlap at the start of the node it enters,
+/// both in the orientation of the edge as stored in the graph.
+/// Alternatively, an edge may span a gap between its endpoints, in which case its overlaps are ignored.
+pub trait GfaEdgeDataExt<LengthType: GraphIndexInteger> {
+    fn tail_overlap(&self) -> GfaPathLength<LengthType>;
+
+    fn head_overlap(&self) -> GfaPathLength<LengthType>;
+
+    /// Returns the length of the gap spanned by the edge, or `None` if the edge is an overlap.
+    fn gap(&self) -> Option<GfaPathLength<LengthType>> {
+        None
+    }
+}
+
+impl<T: GfaEdgeData, LengthType: GraphIndexInteger> GfaEdgeDataExt<LengthType> for T {
+    fn tail_overlap(&self) -> GfaPathLength<LengthType> {
+        GfaPathLength::from_usize(self.overlap().into())
+    }
+
+    fn head_overlap(&self) -> GfaPathLength<LengthType> {
+        GfaPathLength::from_usize(self.overlap().into())
+    }
+}
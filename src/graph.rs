@@ -0,0 +1,399 @@
+use std::marker::PhantomData;
+
+use bidirected_adjacency_array::{
+    graph::BidirectedAdjacencyArray,
+    index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex},
+};
+
+use crate::{
+    gfa_graph_extensions::{GfaEdgeDataExt, GfaNodeDataExt},
+    path::{GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The graph operations required by the queries of this crate.
+///
+/// Nodes are bidirected, and each orientation of a node is addressed by a [`DirectedNodeIndex`].
+/// Node indices must be dense, i.e. all bidirected nodes have indices below [`node_count`](SequenceGraph::node_count).
+pub trait SequenceGraph<IndexType: GraphIndexInteger> {
+    /// The integer type of node lengths, offsets and path lengths.
+    ///
+    /// It is independent of `IndexType`, since paths are usually much longer than the number of nodes in a graph.
+    type LengthType: GraphIndexInteger;
+
+    /// Returns the number of bidirected nodes.
+    fn node_count(&self) -> usize;
+
+    /// Returns the length of the sequence of the node.
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType>;
+
+    /// Iterate over the directed successors of the node, together with the overlaps or gaps of the edges that lead to them.
+    ///
+    /// The overlaps must be given in the direction of traversal,
+    /// i.e. the tail overlap of a successor is the head overlap of the inverse edge.
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>>;
+
+    /// Returns the other orientation of the node.
+    fn invert(&self, node: DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType> {
+        node.invert()
+    }
+}
+
+/// A directed edge as seen from its tail.
+///
+/// The tail overlap is the number of bases the overlap consumes at the end of the node the edge leaves,
+/// and the head overlap is the number of bases it consumes at the start of the successor.
+/// Gap edges, such as scaffolding jumps, have no overlap and instead span a gap of the given length between the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Successor<IndexType, LengthType> {
+    node: DirectedNodeIndex<IndexType>,
+    tail_overlap: GfaPathLength<LengthType>,
+    head_overlap: GfaPathLength<LengthType>,
+    gap: Option<GfaPathLength<LengthType>>,
+}
+
+/// A view of a graph that only contains the nodes accepted by a filter.
+///
+/// Node indices are the same as in the underlying graph.
+/// Edges are contained if both their endpoints are contained.
+pub struct SubgraphView<'graph, Graph, Filter> {
+    graph: &'graph Graph,
+    filter: Filter,
+}
+
+/// A view of a [`BidirectedAdjacencyArray`] whose lengths are of type `LengthType` instead of `u64`.
+///
+/// This allows to use a smaller length type for graphs whose paths are known to be short, or a larger one than the index type.
+pub struct WithLengthType<'graph, Graph, LengthType> {
+    graph: &'graph Graph,
+    length_type: PhantomData<LengthType>,
+}
+
+/// A view of a graph in which each overlap is clamped to the lengths of the nodes it consumes.
+///
+/// This turns graphs with overlaps that exceed the length of a node into graphs where each node contributes a non-negative length.
+pub struct ClampedOverlaps<'graph, Graph> {
+    graph: &'graph Graph,
+}
+
+/// A view of a graph in which the head and tail overlaps of each edge are swapped.
+///
+/// The length of a walk in this view is the length of the reverse complement of the walk in the underlying graph,
+/// since the reverse complement skips the tail instead of the head overlap of each edge.
+pub(crate) struct SwappedOverlaps<'graph, Graph> {
+    graph: &'graph Graph,
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> Successor<IndexType, LengthType> {
+    /// Create a successor whose overlap consumes the same number of bases on both sides.
+    pub fn new(node: DirectedNodeIndex<IndexType>, overlap: GfaPathLength<LengthType>) -> Self {
+        Self::new_asymmetric(node, overlap, overlap)
+    }
+
+    pub fn new_asymmetric(
+        node: DirectedNodeIndex<IndexType>,
+        tail_overlap: GfaPathLength<LengthType>,
+        head_overlap: GfaPathLength<LengthType>,
+    ) -> Self {
+        Self {
+            node,
+            tail_overlap,
+            head_overlap,
+            gap: None,
+        }
+    }
+
+    /// Create a successor that is reached across a gap of the given length.
+    pub fn new_gap(node: DirectedNodeIndex<IndexType>, gap: GfaPathLength<LengthType>) -> Self {
+        Self {
+            gap: Some(gap),
+            ..Self::new(node, GfaPathLength::from_usize(0))
+        }
+    }
+
+    /// Returns the successor of the inverse edge, which leads to `node`.
+    ///
+    /// The inverse edge swaps head and tail overlaps, and spans the same gap.
+    pub(crate) fn inverse(&self, node: DirectedNodeIndex<IndexType>) -> Self {
+        Self {
+            node,
+            tail_overlap: self.head_overlap,
+            head_overlap: self.tail_overlap,
+            gap: self.gap,
+        }
+    }
+
+    /// Returns the same edge leading to `node`, e.g. after renumbering the nodes of a graph.
+    pub(crate) fn with_node(&self, node: DirectedNodeIndex<IndexType>) -> Self {
+        Self { node, ..*self }
+    }
+
+    /// Returns the cost at the end of the successor, given the cost at the end of the node the edge leaves.
+    pub(crate) fn extend_cost(
+        &self,
+        cost: GfaPathLength<LengthType>,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> Result<GfaPathLength<LengthType>, LengthOverflowError> {
+        cost.checked_add(self.gap.unwrap_or(GfaPathLength::from_usize(0)))?
+            .checked_add(graph.node_length(self.node))?
+            .checked_sub(self.head_overlap)
+    }
+
+    /// Returns the cost that traversing the edge adds to a walk, for building indices over edge weights.
+    ///
+    /// A head overlap that exceeds the length of the successor is clamped to it, as in [`ClampedOverlaps`],
+    /// so the weight is never negative.
+    /// Panics if the weight overflows, like the arithmetic operators of [`GfaPathLength`].
+    pub(crate) fn weight(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> GfaPathLength<LengthType> {
+        let node_length = graph.node_length(self.node);
+        let gap = self.gap.unwrap_or(GfaPathLength::from_usize(0));
+        gap + node_length - self.head_overlap.min(node_length)
+    }
+
+    pub fn node(&self) -> DirectedNodeIndex<IndexType> {
+        self.node
+    }
+
+    pub fn tail_overlap(&self) -> GfaPathLength<LengthType> {
+        self.tail_overlap
+    }
+
+    pub fn head_overlap(&self) -> GfaPathLength<LengthType> {
+        self.head_overlap
+    }
+
+    /// Returns the length of the gap spanned by the edge, or `None` if it is an overlap edge.
+    pub fn gap(&self) -> Option<GfaPathLength<LengthType>> {
+        self.gap
+    }
+}
+
+impl<'graph, Graph, Filter> SubgraphView<'graph, Graph, Filter> {
+    pub fn new(graph: &'graph Graph, filter: Filter) -> Self {
+        Self { graph, filter }
+    }
+}
+
+impl<
+    'graph,
+    IndexType: GraphIndexInteger,
+    NodeData: GfaNodeDataExt<u64>,
+    EdgeData: GfaEdgeDataExt<u64>,
+    LengthType: GraphIndexInteger,
+> WithLengthType<'graph, BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>, LengthType>
+{
+    /// Create the view, failing if a node length, overlap or gap of the graph does not fit into `LengthType`.
+    pub fn new(
+        graph: &'graph BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    ) -> Result<Self, LengthOverflowError> {
+        let convert = |length: GfaPathLength<u64>| {
+            GfaPathLength::<LengthType>::checked_from_usize(length.into_usize())
+        };
+        for node in (0..2 * graph.node_count()).map(DirectedNodeIndex::from_usize) {
+            convert(graph.node_length(node))?;
+            for successor in SequenceGraph::iter_successors(graph, node) {
+                convert(successor.tail_overlap())?;
+                convert(successor.head_overlap())?;
+                successor.gap().map(convert).transpose()?;
+            }
+        }
+
+        Ok(Self {
+            graph,
+            length_type: PhantomData,
+        })
+    }
+}
+
+impl<'graph, Graph> ClampedOverlaps<'graph, Graph> {
+    pub fn new(graph: &'graph Graph) -> Self {
+        Self { graph }
+    }
+}
+
+impl<'graph, Graph> SwappedOverlaps<'graph, Graph> {
+    pub(crate) fn new(graph: &'graph Graph) -> Self {
+        Self { graph }
+    }
+}
+
+/// Lengths are `u64`, independently of the index type of the graph.
+/// Use [`WithLengthType`] for other length types.
+impl<IndexType: GraphIndexInteger, NodeData: GfaNodeDataExt<u64>, EdgeData: GfaEdgeDataExt<u64>>
+    SequenceGraph<IndexType> for BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>
+{
+    type LengthType = u64;
+
+    fn node_count(&self) -> usize {
+        BidirectedAdjacencyArray::node_count(self)
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.node_data(node.into_bidirected()).len()
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        adjacency_array_successors(self, node)
+    }
+}
+
+impl<
+    IndexType: GraphIndexInteger,
+    NodeData: GfaNodeDataExt<LengthType>,
+    EdgeData: GfaEdgeDataExt<LengthType>,
+    LengthType: GraphIndexInteger,
+> SequenceGraph<IndexType>
+    for WithLengthType<'_, BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>, LengthType>
+{
+    type LengthType = LengthType;
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.graph.node_data(node.into_bidirected()).len()
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        adjacency_array_successors(self.graph, node)
+    }
+}
+
+fn adjacency_array_successors<
+    IndexType: GraphIndexInteger,
+    NodeData,
+    EdgeData: GfaEdgeDataExt<LengthType>,
+    LengthType: GraphIndexInteger,
+>(
+    graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    node: DirectedNodeIndex<IndexType>,
+) -> impl Iterator<Item = Successor<IndexType, LengthType>> {
+    graph.iter_outgoing_edges(node).map(|edge| {
+        let edge_data = graph.directed_edge_data(edge.index());
+        let (tail_overlap, head_overlap) = (
+            edge_data.data().tail_overlap(),
+            edge_data.data().head_overlap(),
+        );
+        // The inverse of a stored edge swaps head and tail.
+        if let Some(gap) = edge_data.data().gap() {
+            Successor::new_gap(edge.to(), gap)
+        } else if edge_data.is_forward() {
+            Successor::new_asymmetric(edge.to(), tail_overlap, head_overlap)
+        } else {
+            Successor::new_asymmetric(edge.to(), head_overlap, tail_overlap)
+        }
+    })
+}
+
+impl<
+    IndexType: GraphIndexInteger,
+    Graph: SequenceGraph<IndexType>,
+    Filter: Fn(NodeIndex<IndexType>) -> bool,
+> SequenceGraph<IndexType> for SubgraphView<'_, Graph, Filter>
+{
+    type LengthType = Graph::LengthType;
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.graph.node_length(node)
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        let is_contained = (self.filter)(node.into_bidirected());
+        self.graph.iter_successors(node).filter(move |successor| {
+            is_contained && (self.filter)(successor.node().into_bidirected())
+        })
+    }
+
+    fn invert(&self, node: DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType> {
+        self.graph.invert(node)
+    }
+}
+
+impl<IndexType: GraphIndexInteger, Graph: SequenceGraph<IndexType>> SequenceGraph<IndexType>
+    for ClampedOverlaps<'_, Graph>
+{
+    type LengthType = Graph::LengthType;
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.graph.node_length(node)
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        let node_length = self.graph.node_length(node);
+        self.graph.iter_successors(node).map(move |successor| {
+            if successor.gap().is_some() {
+                successor
+            } else {
+                Successor::new_asymmetric(
+                    successor.node(),
+                    successor.tail_overlap().min(node_length),
+                    successor
+                        .head_overlap()
+                        .min(self.graph.node_length(successor.node())),
+                )
+            }
+        })
+    }
+
+    fn invert(&self, node: DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType> {
+        self.graph.invert(node)
+    }
+}
+
+impl<IndexType: GraphIndexInteger, Graph: SequenceGraph<IndexType>> SequenceGraph<IndexType>
+    for SwappedOverlaps<'_, Graph>
+{
+    type LengthType = Graph::LengthType;
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn node_length(&self, node: DirectedNodeIndex<IndexType>) -> GfaPathLength<Self::LengthType> {
+        self.graph.node_length(node)
+    }
+
+    fn iter_successors(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> impl Iterator<Item = Successor<IndexType, Self::LengthType>> {
+        self.graph.iter_successors(node).map(|successor| Successor {
+            tail_overlap: successor.head_overlap,
+            head_overlap: successor.tail_overlap,
+            ..successor
+        })
+    }
+
+    fn invert(&self, node: DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType> {
+        self.graph.invert(node)
+    }
+}
@@ -0,0 +1,100 @@
+use bidirected_adjacency_array::{index::NodeIndex, io::gfa1::read_gfa1};
+
+use crate::{
+    dijkstra::shortest_path,
+    graph::{SequenceGraph, SubgraphView, Successor, WithLengthType},
+    location::GfaLocation,
+    path::{LengthOverflowError, PathElement},
+};
+
+const BUBBLE_GFA: &str = "H\tVN:Z:1.0
+S\tA\tAAA
+S\tB\tC
+S\tC\tGGGG
+S\tD\tTTT
+L\tA\t+\tB\t+\t0M
+L\tA\t+\tC\t+\t1M
+L\tB\t+\tD\t+\t0M
+L\tC\t+\tD\t+\t0M
+";
+
+#[test]
+fn test_bidirected_adjacency_array() {
+    let graph = read_gfa1::<u8>(&mut BUBBLE_GFA.as_bytes()).unwrap();
+
+    assert_eq!(SequenceGraph::node_count(&graph), 4);
+    assert_eq!(graph.node_length(4.into()).into_usize(), 4);
+    assert_eq!(graph.node_length(5.into()).into_usize(), 4);
+    let mut successors = graph.iter_successors(0.into()).collect::<Vec<_>>();
+    successors.sort_by_key(|successor| successor.node());
+    assert_eq!(
+        successors,
+        [
+            Successor::new(2.into(), 0.into()),
+            Successor::new(4.into(), 1.into()),
+        ],
+    );
+    assert_eq!(graph.invert(4.into()), 5.into());
+}
+
+#[test]
+fn test_subgraph_view() {
+    let graph = read_gfa1::<u8>(&mut BUBBLE_GFA.as_bytes()).unwrap();
+    let source = GfaLocation::new(0.into(), 2.into());
+    let target = GfaLocation::new(6.into(), 1.into());
+
+    let path = shortest_path(&graph, source, target).unwrap().unwrap();
+    assert_eq!(path.length().into_usize(), 3);
+
+    let view = SubgraphView::new(&graph, |node: NodeIndex<u8>| node.into_usize() != 1);
+    assert_eq!(view.iter_successors(2.into()).count(), 0);
+    let path = shortest_path(&view, source, target).unwrap().unwrap();
+    assert_eq!(path.length().into_usize(), 5);
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 2.into(), 3.into()),
+            PathElement::new(4.into(), 1.into(), 4.into()),
+            PathElement::new(6.into(), 0.into(), 1.into()),
+        ],
+    );
+
+    let view = SubgraphView::new(&graph, |node: NodeIndex<u8>| node.into_usize() != 3);
+    assert!(shortest_path(&view, source, target).unwrap().is_none());
+}
+
+#[test]
+fn test_with_length_type() {
+    let graph = read_gfa1::<u8>(&mut BUBBLE_GFA.as_bytes()).unwrap();
+    let view = WithLengthType::<_, u16>::new(&graph).unwrap();
+    let path = shortest_path(
+        &view,
+        GfaLocation::new(0.into(), 2.into()),
+        GfaLocation::new(6.into(), 1.into()),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 3);
+
+    // Each node fits into the length type, but the path between them does not.
+    let gfa = format!(
+        "H\tVN:Z:1.0\nS\tA\t{}\nS\tB\t{}\nL\tA\t+\tB\t+\t0M\n",
+        "A".repeat(200),
+        "C".repeat(200),
+    );
+    let graph = read_gfa1::<u8>(&mut gfa.as_bytes()).unwrap();
+    let view = WithLengthType::<_, u8>::new(&graph).unwrap();
+    assert_eq!(
+        shortest_path(
+            &view,
+            GfaLocation::new(0.into(), 0.into()),
+            GfaLocation::new(2.into(), 200.into()),
+        )
+        .err(),
+        Some(LengthOverflowError),
+    );
+
+    let gfa = format!("H\tVN:Z:1.0\nS\tA\t{}\n", "A".repeat(300));
+    let graph = read_gfa1::<u8>(&mut gfa.as_bytes()).unwrap();
+    assert!(WithLengthType::<_, u8>::new(&graph).is_err());
+}
@@ -0,0 +1,471 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Read},
+};
+
+use bidirected_adjacency_array::{
+    graph::BidirectedAdjacencyArray,
+    index::{DirectedNodeIndex, GraphIndexInteger},
+};
+use binary_heap_plus::BinaryHeap;
+use optional_numeric_index::implement_generic_index;
+
+use crate::{
+    dijkstra::{saturation_thresholds, search_state},
+    gfa_graph_extensions::GfaNodeNameExt,
+    graph::{SequenceGraph, Successor},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, LengthOverflowError, PathElement},
+};
+
+#[cfg(test)]
+mod tests;
+
+implement_generic_index!(pub HaplotypeIndex, pub OptionalHaplotypeIndex);
+
+/// A haplotype embedded in a GFA file as a `P` or `W` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Haplotype<IndexType> {
+    name: String,
+    walk: Vec<DirectedNodeIndex<IndexType>>,
+}
+
+/// A set of haplotypes prepared for haplotype-consistent shortest path queries.
+pub struct HaplotypeSet<IndexType, LengthType> {
+    haplotypes: Vec<Haplotype<IndexType>>,
+    /// Each haplotype is traversable in both orientations, so it is represented by two tracks.
+    tracks: Vec<Track<IndexType, LengthType>>,
+    /// Maps each directed node to the track positions at which it occurs.
+    occurrences: HashMap<DirectedNodeIndex<IndexType>, Vec<TrackPosition>>,
+}
+
+/// A path that follows one or more haplotypes.
+pub struct HaplotypePath<IndexType, LengthType> {
+    path: GfaPath<IndexType, LengthType>,
+    /// The haplotype of each node element in `path`.
+    haplotypes: Vec<HaplotypeIndex<IndexType>>,
+    switch_count: usize,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HaplotypeError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("a P line is missing the path name or the segment list")]
+    PLineTooShort,
+
+    #[error("a W line is missing the sample, haplotype, sequence or walk fields")]
+    WLineTooShort,
+
+    #[error("unknown node name '{0}' in a P or W line")]
+    UnknownNodeName(String),
+
+    #[error("malformed step '{0}' in a P line")]
+    MalformedPathStep(String),
+
+    #[error("malformed walk '{0}' in a W line")]
+    MalformedWalk(String),
+
+    #[error("haplotype '{0}' visits no nodes")]
+    EmptyHaplotype(String),
+
+    #[error("haplotype '{haplotype}' uses an edge from {from} to {to} that is not in the graph")]
+    MissingEdge {
+        haplotype: String,
+        from: String,
+        to: String,
+    },
+}
+
+struct Track<IndexType, LengthType> {
+    haplotype: HaplotypeIndex<IndexType>,
+    nodes: Vec<DirectedNodeIndex<IndexType>>,
+    /// The edge entering each node, the first one being a zero overlap.
+    entries: Vec<Successor<IndexType, LengthType>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct TrackPosition {
+    track: usize,
+    position: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SearchState<LengthType> {
+    track_position: TrackPosition,
+    switch_count: usize,
+    /// The cost of the state if it is below the saturation threshold of its node.
+    ///
+    /// Such states cannot be merged with cheaper states, because only the more expensive one might reach the target.
+    unsaturated_cost: Option<GfaPathLength<LengthType>>,
+}
+
+struct Label {
+    track_position: TrackPosition,
+    switch_count: usize,
+    predecessor: Option<usize>,
+    is_switch: bool,
+}
+
+impl<IndexType: GraphIndexInteger> Haplotype<IndexType> {
+    pub fn new(name: impl ToString, walk: Vec<DirectedNodeIndex<IndexType>>) -> Self {
+        Self {
+            name: name.to_string(),
+            walk,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn iter_walk(&self) -> impl Iterator<Item = DirectedNodeIndex<IndexType>> {
+        self.walk.iter().copied()
+    }
+}
+
+/// Read the haplotypes embedded as `P` and `W` lines in a GFA file.
+///
+/// The segment names are resolved against the given graph, which should have been read from the same file.
+/// `W` lines are named in the PanSN format `sample#haplotype#sequence`.
+pub fn read_gfa1_haplotypes<IndexType: GraphIndexInteger, NodeData: GfaNodeNameExt, EdgeData>(
+    graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    reader: &mut impl Read,
+) -> Result<Vec<Haplotype<IndexType>>, HaplotypeError> {
+    let reader = BufReader::new(reader);
+    let node_name_to_node: HashMap<_, _> = graph
+        .iter_nodes()
+        .map(|node| (graph.node_data(node).segment_name().into_owned(), node))
+        .collect();
+    let directed_node = |name: &str, forward: bool| {
+        node_name_to_node
+            .get(name)
+            .map(|node| DirectedNodeIndex::from_bidirected(*node, forward))
+            .ok_or_else(|| HaplotypeError::UnknownNodeName(name.to_string()))
+    };
+    let mut haplotypes = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim().split('\t').collect::<Vec<_>>();
+
+        match line[0] {
+            "P" => {
+                let name = line.get(1).ok_or(HaplotypeError::PLineTooShort)?;
+                let steps = line.get(2).ok_or(HaplotypeError::PLineTooShort)?;
+                // GFA 1.2 separates steps that are connected by a jump with `;` instead of `,`.
+                let walk = steps
+                    .split([',', ';'])
+                    .map(|step| {
+                        if let Some(name) = step.strip_suffix('+') {
+                            directed_node(name, true)
+                        } else if let Some(name) = step.strip_suffix('-') {
+                            directed_node(name, false)
+                        } else {
+                            Err(HaplotypeError::MalformedPathStep(step.to_string()))
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+                haplotypes.push(Haplotype::new(name, walk));
+            }
+
+            "W" => {
+                if line.len() < 7 {
+                    return Err(HaplotypeError::WLineTooShort);
+                }
+                let name = format!("{}#{}#{}", line[1], line[2], line[3]);
+                let steps = line[6];
+
+                let mut walk = Vec::new();
+                let mut remainder = steps;
+                while !remainder.is_empty() {
+                    let forward = match remainder.as_bytes()[0] {
+                        b'>' => true,
+                        b'<' => false,
+                        _ => return Err(HaplotypeError::MalformedWalk(steps.to_string())),
+                    };
+                    remainder = &remainder[1..];
+                    let end = remainder.find(['>', '<']).unwrap_or(remainder.len());
+                    if end == 0 {
+                        return Err(HaplotypeError::MalformedWalk(steps.to_string()));
+                    }
+                    walk.push(directed_node(&remainder[..end], forward)?);
+                    remainder = &remainder[end..];
+                }
+                haplotypes.push(Haplotype::new(name, walk));
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(haplotypes)
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    HaplotypeSet<IndexType, LengthType>
+{
+    /// Prepare the given haplotypes for queries on the given graph.
+    ///
+    /// Fails if a haplotype visits no nodes or uses an edge that does not exist in the graph.
+    /// If multiple edges connect two consecutive nodes, overlaps are preferred over gaps,
+    /// and the one with the largest overlap or the smallest gap is used.
+    pub fn new<NodeData: GfaNodeNameExt, EdgeData>(
+        graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+        haplotypes: Vec<Haplotype<IndexType>>,
+    ) -> Result<Self, HaplotypeError>
+    where
+        BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>:
+            SequenceGraph<IndexType, LengthType = LengthType>,
+    {
+        let mut tracks = Vec::new();
+
+        for (haplotype_index, haplotype) in haplotypes.iter().enumerate() {
+            if haplotype.walk.is_empty() {
+                return Err(HaplotypeError::EmptyHaplotype(haplotype.name.clone()));
+            }
+
+            let haplotype_index = HaplotypeIndex::from_usize(haplotype_index);
+            let forward_nodes = haplotype.walk.clone();
+            let reverse_nodes = haplotype
+                .walk
+                .iter()
+                .rev()
+                .map(|node| graph.invert(*node))
+                .collect();
+
+            for nodes in [forward_nodes, reverse_nodes] {
+                let mut entries = vec![Successor::new(nodes[0], GfaPathLength::from_usize(0))];
+                for window in nodes.windows(2) {
+                    let (from, to) = (window[0], window[1]);
+                    let entry = graph
+                        .iter_successors(from)
+                        .filter(|successor| successor.node() == to)
+                        .min_by_key(|successor| {
+                            (successor.gap(), Reverse(successor.head_overlap()))
+                        })
+                        .ok_or_else(|| HaplotypeError::MissingEdge {
+                            haplotype: haplotype.name.clone(),
+                            from: graph
+                                .node_data(from.into_bidirected())
+                                .segment_name()
+                                .into_owned(),
+                            to: graph
+                                .node_data(to.into_bidirected())
+                                .segment_name()
+                                .into_owned(),
+                        })?;
+                    entries.push(entry);
+                }
+
+                tracks.push(Track {
+                    haplotype: haplotype_index,
+                    nodes,
+                    entries,
+                });
+            }
+        }
+
+        let mut occurrences = HashMap::<_, Vec<_>>::new();
+        for (track_index, track) in tracks.iter().enumerate() {
+            for (position, node) in track.nodes.iter().enumerate() {
+                occurrences.entry(*node).or_default().push(TrackPosition {
+                    track: track_index,
+                    position,
+                });
+            }
+        }
+
+        Ok(Self {
+            haplotypes,
+            tracks,
+            occurrences,
+        })
+    }
+
+    pub fn haplotype(&self, haplotype: HaplotypeIndex<IndexType>) -> &Haplotype<IndexType> {
+        &self.haplotypes[haplotype.into_usize()]
+    }
+
+    pub fn iter_haplotypes(
+        &self,
+    ) -> impl Iterator<Item = (HaplotypeIndex<IndexType>, &Haplotype<IndexType>)> {
+        self.haplotypes
+            .iter()
+            .enumerate()
+            .map(|(index, haplotype)| (HaplotypeIndex::from_usize(index), haplotype))
+    }
+
+    /// Find the shortest path from `source` to `target` that follows the haplotypes.
+    ///
+    /// The path may switch between haplotypes at shared nodes at most `max_switch_count` times.
+    /// Haplotypes can be followed in both orientations.
+    /// Returns `None` if no such path exists, and an error if a cost of the search does not fit into the length type.
+    pub fn shortest_path(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+        max_switch_count: usize,
+    ) -> Result<Option<HaplotypePath<IndexType, LengthType>>, LengthOverflowError> {
+        // Costs are measured from the source to the end of the node of a state.
+        // A state at the target node reaches the target if its cost is at least this threshold.
+        let target_threshold = graph
+            .node_length(target.node())
+            .checked_sub(target.offset().into_length())?;
+        let saturation_thresholds =
+            saturation_thresholds(graph, [(target.node(), target_threshold)])?;
+
+        let mut labels = Vec::new();
+        let mut open_list = BinaryHeap::new_min();
+        let mut closed_list = HashSet::new();
+
+        let source_cost = graph
+            .node_length(source.node())
+            .checked_sub(source.offset().into_length())?;
+        for track_position in self
+            .occurrences
+            .get(&source.node())
+            .into_iter()
+            .flatten()
+            .copied()
+        {
+            open_list.push((source_cost, labels.len()));
+            labels.push(Label {
+                track_position,
+                switch_count: 0,
+                predecessor: None,
+                is_switch: false,
+            });
+        }
+
+        while let Some((cost, label_index)) = open_list.pop() {
+            let TrackPosition { track, position } = labels[label_index].track_position;
+            let switch_count = labels[label_index].switch_count;
+            let node = self.tracks[track].nodes[position];
+
+            let state = SearchState {
+                track_position: labels[label_index].track_position,
+                switch_count,
+                unsaturated_cost: search_state(&saturation_thresholds, node, cost).unsaturated_cost,
+            };
+            if !closed_list.insert(state) {
+                continue;
+            }
+
+            if node == target.node() && cost >= target_threshold {
+                return Ok(Some(self.backtrack(
+                    &labels,
+                    label_index,
+                    source,
+                    cost - target_threshold,
+                    graph,
+                )));
+            }
+
+            // Advance along the track.
+            if let Some(entry) = self.tracks[track].entries.get(position + 1) {
+                let next_cost = entry.extend_cost(cost, graph)?;
+                open_list.push((next_cost, labels.len()));
+                labels.push(Label {
+                    track_position: TrackPosition {
+                        track,
+                        position: position + 1,
+                    },
+                    switch_count,
+                    predecessor: Some(label_index),
+                    is_switch: false,
+                });
+            }
+
+            // Switch to another track at the same node.
+            if switch_count < max_switch_count {
+                for track_position in self.occurrences[&node].iter().copied() {
+                    if track_position == labels[label_index].track_position {
+                        continue;
+                    }
+
+                    open_list.push((cost, labels.len()));
+                    labels.push(Label {
+                        track_position,
+                        switch_count: switch_count + 1,
+                        predecessor: Some(label_index),
+                        is_switch: true,
+                    });
+                }
+            }
+        }
+
+        // Terminated without finding the target.
+        Ok(None)
+    }
+
+    fn backtrack(
+        &self,
+        labels: &[Label],
+        label_index: usize,
+        source: GfaLocation<IndexType, LengthType>,
+        length: GfaPathLength<LengthType>,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> HaplotypePath<IndexType, LengthType> {
+        let mut label_indices = vec![label_index];
+        while let Some(predecessor) = labels[*label_indices.last().unwrap()].predecessor {
+            label_indices.push(predecessor);
+        }
+        label_indices.reverse();
+
+        // Each node is attributed to the haplotype that is used to leave it,
+        // so switches replace the haplotype of the current node.
+        let mut walk = Vec::new();
+        let mut haplotypes = Vec::new();
+        let mut switch_count = 0;
+        for label in label_indices.into_iter().map(|index| &labels[index]) {
+            let TrackPosition { track, position } = label.track_position;
+            let track = &self.tracks[track];
+
+            if label.is_switch {
+                *haplotypes.last_mut().unwrap() = track.haplotype;
+                switch_count += 1;
+            } else {
+                walk.push(track.entries[position]);
+                haplotypes.push(track.haplotype);
+            }
+        }
+
+        HaplotypePath {
+            path: GfaPath::from_walk(walk, source.offset(), length, graph),
+            haplotypes,
+            switch_count,
+        }
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    HaplotypePath<IndexType, LengthType>
+{
+    pub fn path(&self) -> &GfaPath<IndexType, LengthType> {
+        &self.path
+    }
+
+    /// Iterate over the path elements together with the haplotype they were taken from.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            PathElement<IndexType, LengthType>,
+            HaplotypeIndex<IndexType>,
+        ),
+    > {
+        self.path.iter().zip(self.haplotypes.iter().copied())
+    }
+
+    pub fn length(&self) -> GfaPathLength<LengthType> {
+        self.path.length()
+    }
+
+    pub fn switch_count(&self) -> usize {
+        self.switch_count
+    }
+}
@@ -0,0 +1,259 @@
+use bidirected_adjacency_array::io::gfa1::read_gfa1;
+
+use crate::{
+    haplotype::{Haplotype, HaplotypeError, HaplotypeIndex, HaplotypeSet, read_gfa1_haplotypes},
+    io::{LengthMismatchPolicy, UnknownOverlapPolicy, read_gfa1_lengths},
+    location::GfaLocation,
+    path::{PathElement, PathItem},
+};
+
+const BUBBLES_GFA: &str = "H\tVN:Z:1.0
+S\tA\tAAA
+S\tB\tC
+S\tC\tGGGG
+S\tD\tTTT
+S\tE\tAAAAA
+S\tF\tC
+S\tG\tTTT
+L\tA\t+\tB\t+\t0M
+L\tA\t+\tC\t+\t0M
+L\tB\t+\tD\t+\t0M
+L\tC\t+\tD\t+\t0M
+L\tD\t+\tE\t+\t0M
+L\tD\t+\tF\t+\t0M
+L\tE\t+\tG\t+\t0M
+L\tF\t+\tG\t+\t0M
+P\thap1\tA+,B+,D+,E+,G+\t*
+W\tsample\t2\tchr1\t0\t12\t>A>C>D>F>G
+";
+
+#[test]
+fn test_read_haplotypes() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut BUBBLES_GFA.as_bytes()).unwrap();
+
+    assert_eq!(haplotypes.len(), 2);
+    assert_eq!(haplotypes[0].name(), "hap1");
+    assert_eq!(
+        haplotypes[0].iter_walk().collect::<Vec<_>>(),
+        [0, 2, 6, 8, 12].map(Into::into),
+    );
+    assert_eq!(haplotypes[1].name(), "sample#2#chr1");
+    assert_eq!(
+        haplotypes[1].iter_walk().collect::<Vec<_>>(),
+        [0, 4, 6, 10, 12].map(Into::into),
+    );
+}
+
+#[test]
+fn test_read_haplotypes_of_length_graph() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let length_graph = read_gfa1_lengths::<u8>(
+        &mut BUBBLES_GFA.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    assert_eq!(
+        read_gfa1_haplotypes(&length_graph, &mut BUBBLES_GFA.as_bytes()).unwrap(),
+        read_gfa1_haplotypes(&graph, &mut BUBBLES_GFA.as_bytes()).unwrap(),
+    );
+}
+
+#[test]
+fn test_single_haplotype() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = HaplotypeSet::new(&graph, haplotypes).unwrap();
+
+    let path = haplotypes
+        .shortest_path(
+            &graph,
+            GfaLocation::new(0.into(), 1.into()),
+            GfaLocation::new(12.into(), 2.into()),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+
+    let expected_path = vec![
+        (PathElement::new(0.into(), 1.into(), 3.into()), 1.into()),
+        (PathElement::new(4.into(), 0.into(), 4.into()), 1.into()),
+        (PathElement::new(6.into(), 0.into(), 3.into()), 1.into()),
+        (PathElement::new(10.into(), 0.into(), 1.into()), 1.into()),
+        (PathElement::new(12.into(), 0.into(), 2.into()), 1.into()),
+    ];
+
+    assert_eq!(path.length(), 12.into());
+    assert_eq!(path.switch_count(), 0);
+    assert_eq!(path.iter().collect::<Vec<_>>(), expected_path);
+}
+
+#[test]
+fn test_haplotype_switch() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = HaplotypeSet::new(&graph, haplotypes).unwrap();
+
+    let path = haplotypes
+        .shortest_path(
+            &graph,
+            GfaLocation::new(0.into(), 1.into()),
+            GfaLocation::new(12.into(), 2.into()),
+            1,
+        )
+        .unwrap()
+        .unwrap();
+
+    let expected_path: Vec<(_, HaplotypeIndex<_>)> = vec![
+        (PathElement::new(0.into(), 1.into(), 3.into()), 0.into()),
+        (PathElement::new(2.into(), 0.into(), 1.into()), 0.into()),
+        (PathElement::new(6.into(), 0.into(), 3.into()), 1.into()),
+        (PathElement::new(10.into(), 0.into(), 1.into()), 1.into()),
+        (PathElement::new(12.into(), 0.into(), 2.into()), 1.into()),
+    ];
+
+    assert_eq!(path.length(), 9.into());
+    assert_eq!(path.switch_count(), 1);
+    assert_eq!(path.iter().collect::<Vec<_>>(), expected_path);
+}
+
+#[test]
+fn test_reverse_haplotype() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = HaplotypeSet::new(&graph, haplotypes).unwrap();
+
+    let path = haplotypes
+        .shortest_path(
+            &graph,
+            GfaLocation::new(13.into(), 1.into()),
+            GfaLocation::new(1.into(), 2.into()),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(path.length(), 12.into());
+    assert_eq!(
+        path.iter()
+            .map(|(element, _)| element.node())
+            .collect::<Vec<_>>(),
+        [13, 11, 7, 5, 1].map(Into::into),
+    );
+}
+
+#[test]
+fn test_target_not_on_haplotype() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = HaplotypeSet::new(&graph, haplotypes).unwrap();
+
+    assert!(
+        haplotypes
+            .shortest_path(
+                &graph,
+                GfaLocation::new(12.into(), 0.into()),
+                GfaLocation::new(0.into(), 0.into()),
+                2,
+            )
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_empty_haplotype() {
+    let graph = read_gfa1::<u8>(&mut BUBBLES_GFA.as_bytes()).unwrap();
+    let haplotypes = vec![Haplotype::new("empty", Vec::new())];
+
+    assert!(matches!(
+        HaplotypeSet::new(&graph, haplotypes),
+        Err(HaplotypeError::EmptyHaplotype(name)) if name == "empty",
+    ));
+}
+
+#[test]
+fn test_haplotype_across_gap() {
+    // A and B are connected by an overlap and a gap, of which the overlap is used.
+    let gfa = "H\tVN:Z:1.2
+S\tA\tAAA
+S\tB\tCCCC
+S\tC\tGG
+L\tA\t+\tB\t+\t1M
+J\tA\t+\tB\t+\t5
+J\tB\t+\tC\t+\t10
+P\thap1\tA+,B+,C+\t*
+";
+    let graph = read_gfa1_lengths::<u8>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut gfa.as_bytes()).unwrap();
+    let haplotypes = HaplotypeSet::new(&graph, haplotypes).unwrap();
+
+    let path = haplotypes
+        .shortest_path(
+            &graph,
+            GfaLocation::new(0.into(), 0.into()),
+            GfaLocation::new(4.into(), 2.into()),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.length(), 18.into());
+    assert_eq!(
+        path.path().iter_items().collect::<Vec<_>>(),
+        [
+            PathItem::Node(PathElement::new(0.into(), 0.into(), 3.into())),
+            PathItem::Node(PathElement::new(2.into(), 1.into(), 4.into())),
+            PathItem::Gap(10.into()),
+            PathItem::Node(PathElement::new(4.into(), 0.into(), 2.into())),
+        ],
+    );
+}
+
+#[test]
+fn test_target_inside_overlap() {
+    // The target lies inside the overlap from A into B, before the source, so the path has to return to B through C.
+    let gfa = "H\tVN:Z:1.0
+S\tA\tAAA
+S\tB\tCCCC
+S\tC\tGG
+L\tA\t+\tB\t+\t2M
+L\tB\t+\tC\t+\t0M
+L\tC\t+\tB\t+\t0M
+P\thap1\tA+,B+,C+,B+\t*
+";
+    let graph = read_gfa1_lengths::<u8>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    let haplotypes = read_gfa1_haplotypes(&graph, &mut gfa.as_bytes()).unwrap();
+    let haplotypes = HaplotypeSet::new(&graph, haplotypes).unwrap();
+
+    let path = haplotypes
+        .shortest_path(
+            &graph,
+            GfaLocation::new(0.into(), 2.into()),
+            GfaLocation::new(2.into(), 0.into()),
+            0,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.length(), 5.into());
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            (PathElement::new(0.into(), 2.into(), 3.into()), 0.into()),
+            (PathElement::new(2.into(), 2.into(), 4.into()), 0.into()),
+            (PathElement::new(4.into(), 0.into(), 2.into()), 0.into()),
+            (PathElement::new(2.into(), 0.into(), 0.into()), 0.into()),
+        ],
+    );
+}
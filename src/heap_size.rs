@@ -0,0 +1,16 @@
+use std::collections::HashMap;
+
+/// The number of bytes allocated by the vector, not counting allocations owned by its elements.
+pub(crate) fn vec_heap_size<T>(vec: &Vec<T>) -> usize {
+    vec.capacity() * size_of::<T>()
+}
+
+/// The number of bytes allocated by the vector and the vectors it contains.
+pub(crate) fn nested_vec_heap_size<T>(vec: &Vec<Vec<T>>) -> usize {
+    vec_heap_size(vec) + vec.iter().map(vec_heap_size).sum::<usize>()
+}
+
+/// The approximate number of bytes allocated by the hash map, assuming one entry per slot of its capacity.
+pub(crate) fn hash_map_heap_size<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * size_of::<(K, V)>()
+}
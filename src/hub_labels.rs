@@ -0,0 +1,471 @@
+use std::collections::HashSet;
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex};
+use binary_heap_plus::BinaryHeap;
+
+use crate::{
+    dijkstra::shortest_path,
+    graph::{SequenceGraph, Successor},
+    heap_size::{nested_vec_heap_size, vec_heap_size},
+    location::GfaLocation,
+    path::{GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Hubs with their distances, sorted by hub.
+type Label<IndexType, LengthType> = Vec<(DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>)>;
+
+/// A distance oracle based on pruned landmark labelling.
+///
+/// Each directed node stores the distances to the hubs it can reach, and the distances from the hubs that can reach it.
+/// The distance between two nodes is the minimum over their common hubs.
+///
+/// If all overlaps are symmetric, the distance from a hub to a node follows from the distance from the inverted node to the inverted hub,
+/// so only the distances to hubs are stored, and the distances from hubs are derived from them during queries.
+/// Overlaps must not exceed the lengths of the nodes they enter.
+pub struct HubLabels<IndexType, LengthType> {
+    /// The hubs reachable from each directed node.
+    forward_labels: Vec<Label<IndexType, LengthType>>,
+    /// The hubs that reach each directed node, or `None` if they are derived from the forward labels.
+    backward_labels: Option<Vec<Label<IndexType, LengthType>>>,
+    /// The length of each directed node, used to derive backward labels.
+    node_lengths: Vec<GfaPathLength<LengthType>>,
+    /// The position of each directed node in the hub order.
+    hub_order: Vec<usize>,
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> HubLabels<IndexType, LengthType> {
+    /// Compute the labels of all nodes.
+    ///
+    /// Bidirected nodes are used as hubs in order of decreasing degree, and the searches from each hub are pruned
+    /// at nodes whose distance is already covered by the labels of earlier hubs.
+    /// Panics if the distance to a hub does not fit into the length type.
+    pub fn new(graph: &impl SequenceGraph<IndexType, LengthType = LengthType>) -> Self {
+        let directed_node_count = 2 * graph.node_count();
+        let directed_nodes = (0..directed_node_count).map(DirectedNodeIndex::from_usize);
+
+        let mut outgoing = vec![Vec::new(); directed_node_count];
+        let mut incoming = vec![Vec::new(); directed_node_count];
+        let mut is_symmetric = true;
+        for from in directed_nodes.clone() {
+            for successor in graph.iter_successors(from) {
+                let weight = successor.weight(graph);
+                outgoing[from.into_usize()].push((successor.node(), weight));
+                incoming[successor.node().into_usize()].push((from, weight));
+                is_symmetric &= successor.tail_overlap() == successor.head_overlap();
+            }
+        }
+
+        let mut nodes = (0..graph.node_count()).collect::<Vec<_>>();
+        nodes.sort_by_key(|node| {
+            let degree = [2 * node, 2 * node + 1]
+                .map(|node| outgoing[node].len() + incoming[node].len())
+                .iter()
+                .sum::<usize>();
+            (usize::MAX - degree, *node)
+        });
+        // The two orientations of each bidirected node are adjacent in the hub order, starting at an even position.
+        let mut hub_order = vec![0; directed_node_count];
+        let hubs = nodes
+            .iter()
+            .flat_map(|node| {
+                [true, false].map(|forward| {
+                    DirectedNodeIndex::from_bidirected(NodeIndex::from_usize(*node), forward)
+                })
+            })
+            .collect::<Vec<_>>();
+        for (position, hub) in hubs.iter().enumerate() {
+            hub_order[hub.into_usize()] = position;
+        }
+
+        let mut labels = Self {
+            forward_labels: vec![Vec::new(); directed_node_count],
+            backward_labels: (!is_symmetric).then(|| vec![Vec::new(); directed_node_count]),
+            node_lengths: directed_nodes.map(|node| graph.node_length(node)).collect(),
+            hub_order,
+        };
+
+        // With symmetric overlaps, the search towards each orientation of a hub
+        // also covers the search from the other orientation.
+        // Only the labels of earlier bidirected nodes are used for pruning.
+        // The searches of the two orientations of a hub depend on each other if the backward labels are derived,
+        // so pruning with the labels of either orientation could skip nodes that are covered by neither.
+        let zero = GfaPathLength::from_usize(0);
+        for hub in hubs {
+            let hub_limit = labels.hub_order[hub.into_usize()] & !1;
+            labels.pruned_search(
+                hub,
+                (hub, zero),
+                hub_limit,
+                |node| incoming[node.into_usize()].iter().copied(),
+                false,
+            );
+            if !is_symmetric {
+                labels.pruned_search(
+                    hub,
+                    (hub, zero),
+                    hub_limit,
+                    |node| outgoing[node.into_usize()].iter().copied(),
+                    true,
+                );
+            }
+        }
+
+        labels
+    }
+
+    /// Repair the labels after an edge from `from` to the successor, together with its inverse, was added to the graph.
+    ///
+    /// The graph must be the graph of the labels with the edge added.
+    /// Walks over the new edge are covered by resuming the pruned searches of the hubs in the labels of its ends,
+    /// as in the dynamic pruned landmark labelling of Akiba et al. (2014).
+    /// Old entries are updated, but not removed when a shorter entry of another hub covers them,
+    /// so the labels may grow larger than labels built from scratch.
+    ///
+    /// Derived backward labels are stored first: a derived entry answers two pairs of nodes at once,
+    /// and a resumed search may prune it for one pair although the other pair still needs it.
+    /// Panics if the distance to a hub does not fit into the length type.
+    pub(crate) fn insert_edge(
+        &mut self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        from: DirectedNodeIndex<IndexType>,
+        successor: Successor<IndexType, LengthType>,
+    ) {
+        self.store_backward_labels();
+
+        let predecessors = |node: DirectedNodeIndex<IndexType>| {
+            graph
+                .iter_successors(node.invert())
+                .map(move |inverse| (inverse.node().invert(), inverse.inverse(node).weight(graph)))
+        };
+        let successors = |node: DirectedNodeIndex<IndexType>| {
+            graph
+                .iter_successors(node)
+                .map(|successor| (successor.node(), successor.weight(graph)))
+        };
+
+        let inverse_from = successor.node().invert();
+        let inverse = successor.inverse(from.invert());
+        for (tail, edge) in [(from, successor), (inverse_from, inverse)] {
+            let (head, weight) = (edge.node(), edge.weight(graph));
+            for (hub, distance) in self.forward_labels[head.into_usize()].clone() {
+                self.pruned_search(
+                    hub,
+                    (tail, distance + weight),
+                    usize::MAX,
+                    predecessors,
+                    false,
+                );
+            }
+            if let Some(backward_labels) = &self.backward_labels {
+                for (hub, distance) in backward_labels[tail.into_usize()].clone() {
+                    self.pruned_search(
+                        hub,
+                        (head, distance + weight),
+                        usize::MAX,
+                        successors,
+                        true,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Add the nodes of other labels after the nodes of these labels, without edges between them.
+    ///
+    /// The hubs of the other labels follow the hubs of these labels in the hub order.
+    pub(crate) fn append(&mut self, mut other: Self) {
+        if self.backward_labels.is_some() || other.backward_labels.is_some() {
+            self.store_backward_labels();
+            other.store_backward_labels();
+        }
+
+        let offset = self.node_lengths.len();
+        let shift = |label: Label<IndexType, LengthType>| {
+            label
+                .into_iter()
+                .map(|(hub, distance)| {
+                    (
+                        DirectedNodeIndex::from_usize(hub.into_usize() + offset),
+                        distance,
+                    )
+                })
+                .collect::<Label<_, _>>()
+        };
+        self.forward_labels
+            .extend(other.forward_labels.into_iter().map(shift));
+        if let (Some(backward_labels), Some(other_backward_labels)) =
+            (&mut self.backward_labels, other.backward_labels)
+        {
+            backward_labels.extend(other_backward_labels.into_iter().map(shift));
+        }
+        self.node_lengths.extend(other.node_lengths);
+        self.hub_order.extend(
+            other
+                .hub_order
+                .into_iter()
+                .map(|position| position + offset),
+        );
+    }
+
+    /// Returns the total number of stored label entries.
+    ///
+    /// Backward labels that are derived from the forward labels are not stored.
+    pub fn label_count(&self) -> usize {
+        self.forward_labels
+            .iter()
+            .chain(self.backward_labels.iter().flatten())
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Returns the approximate number of bytes that the labels allocate on the heap.
+    pub fn heap_size(&self) -> usize {
+        nested_vec_heap_size(&self.forward_labels)
+            + self
+                .backward_labels
+                .as_ref()
+                .map_or(0, nested_vec_heap_size)
+            + vec_heap_size(&self.node_lengths)
+            + vec_heap_size(&self.hub_order)
+    }
+
+    /// Returns the length of the shortest path from `source` to `target`, or `None` if there is none.
+    ///
+    /// The result is the same as the length of the path returned by [`shortest_path`].
+    /// If the shortest walk between the nodes ends before the target, which can only happen if the target lies before the source within overlaps,
+    /// the query falls back to the search without the labels.
+    /// Returns an error if the length of a walk does not fit into the length type.
+    pub fn distance(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<Option<GfaPathLength<LengthType>>, LengthOverflowError> {
+        let Some(walk_length) = self.node_distance(source.node(), target.node(), usize::MAX)?
+        else {
+            return Ok(None);
+        };
+
+        // Costs are measured from the source to the end of the node, as in the search without the labels.
+        let cost = graph
+            .node_length(source.node())
+            .checked_sub(source.offset().into_length())?
+            .checked_add(walk_length)?;
+        let target_threshold = graph
+            .node_length(target.node())
+            .checked_sub(target.offset().into_length())?;
+        if cost < target_threshold {
+            Ok(shortest_path(graph, source, target)?.map(|path| path.length()))
+        } else {
+            Ok(Some(cost - target_threshold))
+        }
+    }
+
+    /// The length of the shortest walk from the end of `from` to the end of `to` over the hubs before `hub_limit` in the hub order.
+    ///
+    /// The limit must be even, i.e. it must not separate the two orientations of a bidirected node.
+    fn node_distance(
+        &self,
+        from: DirectedNodeIndex<IndexType>,
+        to: DirectedNodeIndex<IndexType>,
+        hub_limit: usize,
+    ) -> Result<Option<GfaPathLength<LengthType>>, LengthOverflowError> {
+        let forward_label = &self.forward_labels[from.into_usize()];
+        if let Some(backward_labels) = &self.backward_labels {
+            return self.merge_labels(
+                forward_label,
+                &backward_labels[to.into_usize()],
+                hub_limit,
+                |hub| hub,
+                |_, distance| Ok(distance),
+            );
+        }
+
+        // With symmetric overlaps, the inverse of a walk from x to y is longer by the length of x minus the length of y.
+        // So the distance from a hub to the node is the distance from the inverted node to the inverted hub,
+        // plus the length of the node minus the length of the hub.
+        let node_length = self.node_lengths[to.into_usize()];
+        self.merge_labels(
+            forward_label,
+            &self.forward_labels[to.invert().into_usize()],
+            hub_limit,
+            DirectedNodeIndex::invert,
+            |hub, distance| {
+                Ok(distance.checked_add(node_length)? - self.node_lengths[hub.into_usize()])
+            },
+        )
+    }
+
+    /// The minimum distance over the common hubs of a forward and a backward label.
+    ///
+    /// The backward label is given by `backward_label`, with each hub mapped by `backward_hub` and each distance by `backward_distance`.
+    /// Both labels must be sorted by the bidirected nodes of their hubs in the hub order.
+    fn merge_labels(
+        &self,
+        forward_label: &Label<IndexType, LengthType>,
+        backward_label: &Label<IndexType, LengthType>,
+        hub_limit: usize,
+        backward_hub: impl Fn(DirectedNodeIndex<IndexType>) -> DirectedNodeIndex<IndexType>,
+        backward_distance: impl Fn(
+            DirectedNodeIndex<IndexType>,
+            GfaPathLength<LengthType>,
+        ) -> Result<GfaPathLength<LengthType>, LengthOverflowError>,
+    ) -> Result<Option<GfaPathLength<LengthType>>, LengthOverflowError> {
+        // Inverting the hubs of a label swaps the two orientations of each bidirected node in the hub order,
+        // so common hubs are found by merging on bidirected nodes, and comparing the at most two entries of each.
+        let bidirected_position =
+            |hub: DirectedNodeIndex<IndexType>| self.hub_order[hub.into_usize()] / 2;
+        let (mut forward_index, mut backward_index) = (0, 0);
+        let mut distance = None;
+        while let (Some((forward_hub, _)), Some((backward_label_hub, _))) = (
+            forward_label.get(forward_index),
+            backward_label.get(backward_index),
+        ) {
+            let forward_position = bidirected_position(*forward_hub);
+            let backward_position = bidirected_position(backward_hub(*backward_label_hub));
+            if 2 * forward_position.max(backward_position) >= hub_limit {
+                break;
+            }
+            if forward_position < backward_position {
+                forward_index += 1;
+                continue;
+            }
+            if backward_position < forward_position {
+                backward_index += 1;
+                continue;
+            }
+
+            let forward_end = forward_index
+                + forward_label[forward_index..]
+                    .iter()
+                    .take_while(|(hub, _)| bidirected_position(*hub) == forward_position)
+                    .count();
+            let backward_end = backward_index
+                + backward_label[backward_index..]
+                    .iter()
+                    .take_while(|(hub, _)| {
+                        bidirected_position(backward_hub(*hub)) == backward_position
+                    })
+                    .count();
+            for (forward_hub, forward_distance) in &forward_label[forward_index..forward_end] {
+                for (hub, hub_distance) in &backward_label[backward_index..backward_end] {
+                    let hub = backward_hub(*hub);
+                    if hub == *forward_hub {
+                        let candidate =
+                            forward_distance.checked_add(backward_distance(hub, *hub_distance)?)?;
+                        if distance.is_none_or(|distance| candidate < distance) {
+                            distance = Some(candidate);
+                        }
+                    }
+                }
+            }
+            (forward_index, backward_index) = (forward_end, backward_end);
+        }
+
+        Ok(distance)
+    }
+
+    /// Search from `start` and add the hub to the labels of all nodes whose distance is not yet covered.
+    ///
+    /// The search starts at the given distance, and is pruned with the labels of the hubs before `hub_limit` in the hub order,
+    /// and at nodes whose label already contains the hub with at most the same distance.
+    /// If `is_forward` is set, the search follows `neighbours` away from the hub and fills backward labels,
+    /// otherwise it follows `neighbours` towards the hub and fills forward labels.
+    fn pruned_search<
+        Neighbours: IntoIterator<Item = (DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>)>,
+    >(
+        &mut self,
+        hub: DirectedNodeIndex<IndexType>,
+        (start, start_distance): (DirectedNodeIndex<IndexType>, GfaPathLength<LengthType>),
+        hub_limit: usize,
+        neighbours: impl Fn(DirectedNodeIndex<IndexType>) -> Neighbours,
+        is_forward: bool,
+    ) {
+        let mut open_list = BinaryHeap::new_min();
+        let mut closed_list = HashSet::new();
+        open_list.push((start_distance, start));
+
+        while let Some((distance, node)) = open_list.pop() {
+            if !closed_list.insert(node) {
+                continue;
+            }
+
+            // A walk over earlier hubs whose length overflows is longer than the distance, so it does not cover the node.
+            let covered_distance = if is_forward {
+                self.node_distance(hub, node, hub_limit)
+            } else {
+                self.node_distance(node, hub, hub_limit)
+            }
+            .ok()
+            .flatten();
+            if covered_distance.is_some_and(|covered_distance| covered_distance <= distance) {
+                continue;
+            }
+
+            let label = if is_forward {
+                &mut self.backward_labels.as_mut().unwrap()[node.into_usize()]
+            } else {
+                &mut self.forward_labels[node.into_usize()]
+            };
+            if !improve_entry(label, &self.hub_order, hub, distance) {
+                continue;
+            }
+
+            for (next, weight) in neighbours(node) {
+                if !closed_list.contains(&next) {
+                    open_list.push((distance + weight, next));
+                }
+            }
+        }
+    }
+
+    /// Store the backward labels if they are derived from the forward labels.
+    fn store_backward_labels(&mut self) {
+        if self.backward_labels.is_some() {
+            return;
+        }
+
+        // With symmetric overlaps, the distance from the inverted hub to the inverted node is the distance from the node to the hub,
+        // plus the length of the inverted node minus the length of the inverted hub.
+        let mut backward_labels = vec![Vec::new(); self.forward_labels.len()];
+        for (node, label) in self.forward_labels.iter().enumerate() {
+            let node = DirectedNodeIndex::<IndexType>::from_usize(node).invert();
+            for (hub, distance) in label {
+                let hub = hub.invert();
+                let distance = *distance + self.node_lengths[node.into_usize()]
+                    - self.node_lengths[hub.into_usize()];
+                backward_labels[node.into_usize()].push((hub, distance));
+            }
+        }
+        for label in &mut backward_labels {
+            label.sort_unstable_by_key(|(hub, _)| self.hub_order[hub.into_usize()]);
+        }
+        self.backward_labels = Some(backward_labels);
+    }
+}
+
+/// Add the hub to the label or lower its distance, keeping the label sorted by hub.
+///
+/// Returns false if the label already contains the hub with at most the given distance.
+fn improve_entry<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    label: &mut Label<IndexType, LengthType>,
+    hub_order: &[usize],
+    hub: DirectedNodeIndex<IndexType>,
+    distance: GfaPathLength<LengthType>,
+) -> bool {
+    let position = hub_order[hub.into_usize()];
+    let index = label.partition_point(|(other, _)| hub_order[other.into_usize()] < position);
+    match label.get_mut(index) {
+        Some(entry) if entry.0 == hub => {
+            if entry.1 <= distance {
+                return false;
+            }
+            entry.1 = distance;
+        }
+        _ => label.insert(index, (hub, distance)),
+    }
+    true
+}
@@ -0,0 +1,89 @@
+use bidirected_adjacency_array::index::DirectedNodeIndex;
+
+use crate::{
+    dynamic::DynamicGraph,
+    graph::SequenceGraph,
+    hub_labels::HubLabels,
+    io::{LengthMismatchPolicy, UnknownOverlapPolicy, read_gfa1_lengths},
+    test_util::{assert_same_distances, random_graph},
+};
+
+#[test]
+fn test_random_graphs() {
+    for seed in 0..4 {
+        let graph = random_graph(seed, 16, 32);
+        let labels = HubLabels::new(&graph);
+        // Each directed node is at least its own hub.
+        assert!(labels.label_count() >= 2 * graph.node_count());
+        assert_same_distances(&graph, |source, target| {
+            labels.distance(&graph, source, target).unwrap()
+        });
+    }
+}
+
+#[test]
+fn test_asymmetric_overlaps() {
+    let gfa = "S\tA\t*\tLN:i:10\nS\tB\t*\tLN:i:6\nS\tC\t*\tLN:i:4\n\
+        L\tA\t+\tB\t+\t3M2D\nL\tB\t+\tC\t-\t1M1I\nL\tC\t-\tA\t-\t2M\nJ\tB\t-\tA\t+\t5\n";
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    let labels = HubLabels::new(&graph);
+    assert_same_distances(&graph, |source, target| {
+        labels.distance(&graph, source, target).unwrap()
+    });
+}
+
+#[test]
+fn test_insert_edges() {
+    for seed in 0..4 {
+        // The graph without edges has the same node lengths.
+        let full_graph = random_graph(seed, 16, 32);
+        let mut graph = DynamicGraph::from_graph(&random_graph(seed, 16, 0));
+        let mut labels = HubLabels::new(&graph);
+        for from in (0..2 * full_graph.node_count()).map(DirectedNodeIndex::from_usize) {
+            for successor in full_graph.iter_successors(from) {
+                graph.add_edge(from, successor);
+                labels.insert_edge(&graph, from, successor);
+            }
+        }
+        assert_same_distances(&graph, |source, target| {
+            labels.distance(&graph, source, target).unwrap()
+        });
+    }
+}
+
+#[test]
+fn test_insert_asymmetric_edges() {
+    let gfa = "S\tA\t*\tLN:i:10\nS\tB\t*\tLN:i:6\nS\tC\t*\tLN:i:4\n\
+        L\tA\t+\tB\t+\t3M2D\nL\tB\t+\tC\t-\t1M1I\nL\tC\t-\tA\t-\t2M\nJ\tB\t-\tA\t+\t5\n";
+    let full_graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    // Without the asymmetric edges, the backward labels are derived from the forward labels.
+    let mut graph = DynamicGraph::from_graph(&full_graph);
+    let mut asymmetric_edges = Vec::new();
+    for from in (0..2 * full_graph.node_count()).map(DirectedNodeIndex::from_usize) {
+        for successor in full_graph.iter_successors(from) {
+            if successor.tail_overlap() != successor.head_overlap() {
+                graph.remove_edge(from, successor.node());
+                asymmetric_edges.push((from, successor));
+            }
+        }
+    }
+    let mut labels = HubLabels::new(&graph);
+    for (from, successor) in asymmetric_edges {
+        graph.add_edge(from, successor);
+        labels.insert_edge(&graph, from, successor);
+    }
+    assert_same_distances(&graph, |source, target| {
+        labels.distance(&graph, source, target).unwrap()
+    });
+}
@@ -0,0 +1,483 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+};
+
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex},
+};
+
+use crate::{
+    gfa_graph_extensions::{GfaEdgeDataExt, GfaNodeDataExt, GfaNodeNameExt},
+    path::GfaPathLength,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Node data that stores only the name and the length of a GFA segment.
+///
+/// This allows to build graphs of assemblies whose segments do not have sequences,
+/// or to avoid keeping the sequences in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthGfaNodeData {
+    name: String,
+    length: usize,
+}
+
+/// Edge data that stores the number of bases an overlap consumes on each of its endpoints.
+///
+/// These differ if the overlap CIGAR contains insertions or deletions.
+/// Edges from J or G lines instead store the distance of the gap between their endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CigarGfaEdgeData {
+    tail_overlap: usize,
+    head_overlap: usize,
+    gap: Option<usize>,
+}
+
+/// What to do with edges whose overlap or gap distance is `*`, i.e. unspecified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnknownOverlapPolicy {
+    /// Fail with [`GfaLengthReadError::UnknownOverlap`] or [`GfaLengthReadError::UnknownGap`].
+    Error,
+
+    /// Treat the overlap or gap as zero.
+    ///
+    /// An overlap can only shorten a walk, so distances across overlaps of unknown length are then upper bounds.
+    /// A gap can only lengthen a walk, so distances across gaps of unknown length are then lower bounds.
+    AssumeZero,
+}
+
+/// What to do if a segment has both a sequence and an `LN` tag, and they disagree on the length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthMismatchPolicy {
+    /// Fail with [`GfaLengthReadError::LengthMismatch`].
+    Error,
+
+    /// Use the length given by the `LN` tag.
+    PreferLengthTag,
+
+    /// Use the length of the sequence.
+    PreferSequence,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GfaLengthReadError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("an S line is missing the sequence name")]
+    MissingSequenceNameInSLine,
+
+    #[error("segment '{0}' has neither a sequence nor an LN tag")]
+    MissingLength(String),
+
+    #[error("segment '{name}' has a malformed LN tag '{tag}'")]
+    MalformedLengthTag { name: String, tag: String },
+
+    #[error(
+        "segment '{name}' has an LN tag of {tag_length}, but its sequence has length {sequence_length}"
+    )]
+    LengthMismatch {
+        name: String,
+        tag_length: usize,
+        sequence_length: usize,
+    },
+
+    #[error("an L or J line is missing the four fields specifying the edge endpoints")]
+    LLineTooShort,
+
+    #[error("a G line is missing the segment references or the distance")]
+    GLineTooShort,
+
+    #[error("an E line is missing the segment references or the positions of the alignment")]
+    ELineTooShort,
+
+    #[error("unknown node name '{0}' in an L, J, G or E line")]
+    UnknownNodeName(String),
+
+    #[error("unknown sign '{0}' in an L, J, G or E line")]
+    UnknownGfaNodeSign(String),
+
+    #[error("malformed position '{0}' in an E line")]
+    MalformedEdgePosition(String),
+
+    #[error(
+        "the E line '{0}' is not a dovetail overlap, such as a containment, which cannot be represented as an edge"
+    )]
+    NonDovetailEdge(String),
+
+    #[error("malformed gap distance '{0}' in a J or G line")]
+    MalformedGapDistance(String),
+
+    #[error("malformed overlap CIGAR '{0}' in an L line")]
+    MalformedCigar(String),
+
+    #[error("the L line from '{from}' to '{to}' has an unknown overlap")]
+    UnknownOverlap { from: String, to: String },
+
+    #[error("the gap from '{from}' to '{to}' has an unknown distance")]
+    UnknownGap { from: String, to: String },
+}
+
+/// Read a GFA1 or GFA2 file, keeping only the names and lengths of the segments.
+///
+/// Despite its name, this also reads GFA2 files, as detected by their header, see below.
+/// The length of a segment is taken from its `LN` tag or from its sequence, where a sequence of `*` is treated as missing.
+/// If both are present and disagree, `mismatch_policy` decides which one is used.
+/// Overlaps are parsed from their full CIGAR strings, see [`parse_overlap_cigar`],
+/// and `unknown_overlap_policy` decides how `*` overlaps and gap distances are handled.
+///
+/// Besides S and L lines, the J lines of GFA 1.2 are read as gap edges.
+/// If the header declares version 2.0, the file is read as GFA2 instead,
+/// where the length of a segment is given by its length field, E lines are read as overlap edges and G lines as gap edges.
+/// E lines must describe dovetail overlaps, i.e. the alignment must reach the end of the tail and the start of the head in the orientation of the edge.
+/// The overlap is given by the positions of the alignment, and its CIGAR string is ignored.
+/// Other lines are ignored.
+/// Gap distances must not be negative.
+pub fn read_gfa1_lengths<IndexType: GraphIndexInteger>(
+    reader: &mut impl Read,
+    mismatch_policy: LengthMismatchPolicy,
+    unknown_overlap_policy: UnknownOverlapPolicy,
+) -> Result<
+    BidirectedAdjacencyArray<IndexType, LengthGfaNodeData, CigarGfaEdgeData>,
+    GfaLengthReadError,
+> {
+    let reader = BufReader::new(reader);
+    let mut node_name_to_node = HashMap::new();
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    let mut is_gfa2 = false;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim().split('\t').collect::<Vec<_>>();
+
+        match line[0] {
+            "H" => {
+                if line.contains(&"VN:Z:2.0") {
+                    is_gfa2 = true;
+                }
+            }
+
+            "S" => {
+                let name = line
+                    .get(1)
+                    .ok_or(GfaLengthReadError::MissingSequenceNameInSLine)?
+                    .to_string();
+                // GFA2 has a length field before the sequence, which takes the role of the `LN` tag.
+                let sequence_column = if is_gfa2 { 3 } else { 2 };
+                let sequence_length = line
+                    .get(sequence_column)
+                    .filter(|sequence| **sequence != "*")
+                    .map(|sequence| sequence.len());
+                let tag_length = if is_gfa2 {
+                    line.get(2)
+                        .map(|length| {
+                            length.parse::<usize>().map_err(|_| {
+                                GfaLengthReadError::MalformedLengthTag {
+                                    name: name.clone(),
+                                    tag: length.to_string(),
+                                }
+                            })
+                        })
+                        .transpose()?
+                } else {
+                    line.iter()
+                        .skip(3)
+                        .find_map(|tag| tag.strip_prefix("LN:"))
+                        .map(|tag| {
+                            tag.strip_prefix("i:")
+                                .and_then(|length| length.parse::<usize>().ok())
+                                .ok_or_else(|| GfaLengthReadError::MalformedLengthTag {
+                                    name: name.clone(),
+                                    tag: format!("LN:{tag}"),
+                                })
+                        })
+                        .transpose()?
+                };
+
+                let length = match (tag_length, sequence_length) {
+                    (Some(tag_length), Some(sequence_length)) if tag_length != sequence_length => {
+                        match mismatch_policy {
+                            LengthMismatchPolicy::Error => {
+                                return Err(GfaLengthReadError::LengthMismatch {
+                                    name,
+                                    tag_length,
+                                    sequence_length,
+                                });
+                            }
+                            LengthMismatchPolicy::PreferLengthTag => tag_length,
+                            LengthMismatchPolicy::PreferSequence => sequence_length,
+                        }
+                    }
+                    (Some(length), _) | (None, Some(length)) => length,
+                    (None, None) => return Err(GfaLengthReadError::MissingLength(name)),
+                };
+
+                node_name_to_node.insert(
+                    name.clone(),
+                    NodeIndex::<IndexType>::from_usize(nodes.len()),
+                );
+                nodes.push(LengthGfaNodeData { name, length });
+            }
+
+            "L" | "J" => {
+                let node = |name_column: usize, sign_column: usize| {
+                    let name = line
+                        .get(name_column)
+                        .ok_or(GfaLengthReadError::LLineTooShort)?;
+                    let sign = line
+                        .get(sign_column)
+                        .ok_or(GfaLengthReadError::LLineTooShort)?;
+                    directed_node(&node_name_to_node, name, sign)
+                };
+                let from = node(1, 2)?;
+                let to = node(3, 4)?;
+
+                let data = if line[0] == "J" {
+                    let distance = line.get(5).ok_or(GfaLengthReadError::LLineTooShort)?;
+                    gap_edge_data(distance, line[1], line[3], unknown_overlap_policy)?
+                } else {
+                    let cigar = line.get(5).unwrap_or(&"0M");
+                    match parse_overlap_cigar(cigar)? {
+                        Some((tail_overlap, head_overlap)) => {
+                            CigarGfaEdgeData::new(tail_overlap, head_overlap)
+                        }
+                        None => match unknown_overlap_policy {
+                            UnknownOverlapPolicy::Error => {
+                                return Err(GfaLengthReadError::UnknownOverlap {
+                                    from: line[1].to_string(),
+                                    to: line[3].to_string(),
+                                });
+                            }
+                            UnknownOverlapPolicy::AssumeZero => CigarGfaEdgeData::new(0, 0),
+                        },
+                    }
+                };
+
+                edges.push(BidirectedEdge::new(from, to, data));
+            }
+
+            "E" if is_gfa2 => {
+                let field =
+                    |column: usize| line.get(column).ok_or(GfaLengthReadError::ELineTooShort);
+                let (_, from) = segment_reference(&node_name_to_node, field(2)?)?;
+                let (_, to) = segment_reference(&node_name_to_node, field(3)?)?;
+                let [tail_begin, tail_end, head_begin, head_end] = [4, 5, 6, 7]
+                    .map(|column| field(column).and_then(|position| edge_position(position)));
+                let ((tail_begin, _), (tail_end, is_tail_end)) = (tail_begin?, tail_end?);
+                let ((head_begin, _), (head_end, is_head_end)) = (head_begin?, head_end?);
+
+                // The alignment must cover the end of the tail and the start of the head in the orientation of the edge.
+                let covers_tail_end = if from.is_forward() {
+                    is_tail_end
+                } else {
+                    tail_begin == 0
+                };
+                let covers_head_start = if to.is_forward() {
+                    head_begin == 0
+                } else {
+                    is_head_end
+                };
+                if !covers_tail_end
+                    || !covers_head_start
+                    || tail_begin > tail_end
+                    || head_begin > head_end
+                {
+                    return Err(GfaLengthReadError::NonDovetailEdge(field(1)?.to_string()));
+                }
+
+                let data = CigarGfaEdgeData::new(tail_end - tail_begin, head_end - head_begin);
+                edges.push(BidirectedEdge::new(from, to, data));
+            }
+
+            "G" if is_gfa2 => {
+                let node = |column: usize| {
+                    let reference = line.get(column).ok_or(GfaLengthReadError::GLineTooShort)?;
+                    segment_reference(&node_name_to_node, reference)
+                };
+                let (from_name, from) = node(2)?;
+                let (to_name, to) = node(3)?;
+                let distance = line.get(4).ok_or(GfaLengthReadError::GLineTooShort)?;
+                let data = gap_edge_data(distance, from_name, to_name, unknown_overlap_policy)?;
+                edges.push(BidirectedEdge::new(from, to, data));
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(BidirectedAdjacencyArray::new(
+        nodes.into(),
+        FromIterator::from_iter(edges),
+    ))
+}
+
+fn directed_node<IndexType: GraphIndexInteger>(
+    node_name_to_node: &HashMap<String, NodeIndex<IndexType>>,
+    name: &str,
+    sign: &str,
+) -> Result<DirectedNodeIndex<IndexType>, GfaLengthReadError> {
+    let node = node_name_to_node
+        .get(name)
+        .copied()
+        .ok_or_else(|| GfaLengthReadError::UnknownNodeName(name.to_string()))?;
+    let forward = match sign {
+        "+" => true,
+        "-" => false,
+        other => return Err(GfaLengthReadError::UnknownGfaNodeSign(other.to_string())),
+    };
+    Ok(DirectedNodeIndex::from_bidirected(node, forward))
+}
+
+/// Parse a GFA2 segment reference, which carries its orientation as a suffix, e.g. `A+`.
+fn segment_reference<'reference, IndexType: GraphIndexInteger>(
+    node_name_to_node: &HashMap<String, NodeIndex<IndexType>>,
+    reference: &'reference str,
+) -> Result<(&'reference str, DirectedNodeIndex<IndexType>), GfaLengthReadError> {
+    let split = reference
+        .char_indices()
+        .last()
+        .map_or(0, |(index, _)| index);
+    let (name, sign) = reference.split_at(split);
+    directed_node(node_name_to_node, name, sign).map(|node| (name, node))
+}
+
+/// Parse a position of an E line, returning whether it is marked with `$` as the end of its segment.
+fn edge_position(position: &str) -> Result<(usize, bool), GfaLengthReadError> {
+    let (value, is_end) = match position.strip_suffix('$') {
+        Some(value) => (value, true),
+        None => (position, false),
+    };
+    value
+        .parse::<usize>()
+        .map(|value| (value, is_end))
+        .map_err(|_| GfaLengthReadError::MalformedEdgePosition(position.to_string()))
+}
+
+/// Parse the distance of a J or G line into the data of a gap edge.
+fn gap_edge_data(
+    distance: &str,
+    from: &str,
+    to: &str,
+    unknown_overlap_policy: UnknownOverlapPolicy,
+) -> Result<CigarGfaEdgeData, GfaLengthReadError> {
+    if distance == "*" {
+        return match unknown_overlap_policy {
+            UnknownOverlapPolicy::Error => Err(GfaLengthReadError::UnknownGap {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            UnknownOverlapPolicy::AssumeZero => Ok(CigarGfaEdgeData::new_gap(0)),
+        };
+    }
+
+    distance
+        .parse::<usize>()
+        .map(CigarGfaEdgeData::new_gap)
+        .map_err(|_| GfaLengthReadError::MalformedGapDistance(distance.to_string()))
+}
+
+/// Compute the number of bases that an overlap CIGAR consumes at the end of the tail and at the start of the head of an edge.
+///
+/// The tail is treated as the reference and the head as the query,
+/// so `M`, `D`, `N`, `=` and `X` consume the tail, and `M`, `I`, `S`, `=` and `X` consume the head.
+/// Returns `None` for an unknown overlap `*`.
+pub fn parse_overlap_cigar(cigar: &str) -> Result<Option<(usize, usize)>, GfaLengthReadError> {
+    if cigar == "*" {
+        return Ok(None);
+    }
+
+    let malformed = || GfaLengthReadError::MalformedCigar(cigar.to_string());
+    let mut tail_overlap = 0;
+    let mut head_overlap = 0;
+    let mut remaining = cigar;
+    while !remaining.is_empty() {
+        let operation_index = remaining
+            .find(|character: char| !character.is_ascii_digit())
+            .ok_or_else(malformed)?;
+        let count = remaining[..operation_index]
+            .parse::<usize>()
+            .map_err(|_| malformed())?;
+        let operation = remaining[operation_index..].chars().next().unwrap();
+
+        let (consumes_tail, consumes_head) = match operation {
+            'M' | '=' | 'X' => (true, true),
+            'D' | 'N' => (true, false),
+            'I' | 'S' => (false, true),
+            'H' | 'P' => (false, false),
+            _ => return Err(malformed()),
+        };
+        if consumes_tail {
+            tail_overlap += count;
+        }
+        if consumes_head {
+            head_overlap += count;
+        }
+
+        remaining = &remaining[operation_index + operation.len_utf8()..];
+    }
+
+    Ok(Some((tail_overlap, head_overlap)))
+}
+
+impl LengthGfaNodeData {
+    pub fn new(name: impl ToString, length: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            length,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl GfaNodeNameExt for LengthGfaNodeData {
+    fn segment_name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.name)
+    }
+}
+
+impl<LengthType: GraphIndexInteger> GfaNodeDataExt<LengthType> for LengthGfaNodeData {
+    fn len(&self) -> GfaPathLength<LengthType> {
+        GfaPathLength::from_usize(self.length)
+    }
+}
+
+impl CigarGfaEdgeData {
+    pub fn new(tail_overlap: usize, head_overlap: usize) -> Self {
+        Self {
+            tail_overlap,
+            head_overlap,
+            gap: None,
+        }
+    }
+
+    /// Create the data of an edge that spans a gap of the given distance.
+    pub fn new_gap(gap: usize) -> Self {
+        Self {
+            gap: Some(gap),
+            ..Self::new(0, 0)
+        }
+    }
+}
+
+impl<LengthType: GraphIndexInteger> GfaEdgeDataExt<LengthType> for CigarGfaEdgeData {
+    fn tail_overlap(&self) -> GfaPathLength<LengthType> {
+        GfaPathLength::from_usize(self.tail_overlap)
+    }
+
+    fn head_overlap(&self) -> GfaPathLength<LengthType> {
+        GfaPathLength::from_usize(self.head_overlap)
+    }
+
+    fn gap(&self) -> Option<GfaPathLength<LengthType>> {
+        self.gap.map(GfaPathLength::from_usize)
+    }
+}
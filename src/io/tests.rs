@@ -0,0 +1,333 @@
+use crate::{
+    dijkstra::{StrandCombination, shortest_path, strand_agnostic_shortest_path},
+    graph::SequenceGraph,
+    io::{
+        GfaLengthReadError, LengthMismatchPolicy, UnknownOverlapPolicy, parse_overlap_cigar,
+        read_gfa1_lengths,
+    },
+    location::GfaLocation,
+    path::{GfaPathLength, PathElement, PathItem},
+};
+
+const LENGTHS_GFA: &str = "H\tVN:Z:1.0
+S\tA\t*\tLN:i:1000
+S\tB\tACGT
+S\tC\tACG\tRC:i:12\tLN:i:5
+L\tA\t+\tB\t+\t0M
+L\tB\t+\tC\t-\t2M
+";
+
+#[test]
+fn test_read_lengths() {
+    let graph = read_gfa1_lengths::<u16>(
+        &mut LENGTHS_GFA.as_bytes(),
+        LengthMismatchPolicy::PreferLengthTag,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    assert_eq!(graph.node_data(0.into()).name(), "A");
+    assert_eq!(
+        (0..3)
+            .map(|node| graph.node_length((2 * node).into()).into_usize())
+            .collect::<Vec<_>>(),
+        [1000, 4, 5],
+    );
+
+    let path = shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 990.into()),
+        GfaLocation::new(5.into(), 4.into()),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 10 + 4 + 2);
+}
+
+#[test]
+fn test_length_mismatch_policy() {
+    let graph = read_gfa1_lengths::<u16>(
+        &mut LENGTHS_GFA.as_bytes(),
+        LengthMismatchPolicy::PreferSequence,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    assert_eq!(graph.node_length(4.into()).into_usize(), 3);
+
+    let result = read_gfa1_lengths::<u16>(
+        &mut LENGTHS_GFA.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(
+        result,
+        Err(GfaLengthReadError::LengthMismatch {
+            tag_length: 5,
+            sequence_length: 3,
+            ..
+        }),
+    ));
+}
+
+#[test]
+fn test_missing_length() {
+    let gfa = "S\tA\t*\nS\tB\t*\tLN:Z:7\n";
+    let result = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(result, Err(GfaLengthReadError::MissingLength(name)) if name == "A"));
+
+    let gfa = "S\tB\t*\tLN:Z:7\n";
+    let result = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(
+        result,
+        Err(GfaLengthReadError::MalformedLengthTag { .. })
+    ));
+}
+
+#[test]
+fn test_parse_overlap_cigar() {
+    assert_eq!(parse_overlap_cigar("0M").unwrap(), Some((0, 0)));
+    assert_eq!(parse_overlap_cigar("5M").unwrap(), Some((5, 5)));
+    assert_eq!(parse_overlap_cigar("3M2D1I").unwrap(), Some((5, 4)));
+    assert_eq!(parse_overlap_cigar("2=1X1N2S1H").unwrap(), Some((4, 5)));
+    assert_eq!(parse_overlap_cigar("*").unwrap(), None);
+    assert!(matches!(
+        parse_overlap_cigar("3M2"),
+        Err(GfaLengthReadError::MalformedCigar(_))
+    ));
+    assert!(matches!(
+        parse_overlap_cigar("M"),
+        Err(GfaLengthReadError::MalformedCigar(_))
+    ));
+    assert!(matches!(
+        parse_overlap_cigar("3Q"),
+        Err(GfaLengthReadError::MalformedCigar(_))
+    ));
+}
+
+#[test]
+fn test_asymmetric_overlap() {
+    let gfa = "S\tA\t*\tLN:i:10\nS\tB\t*\tLN:i:10\nL\tA\t+\tB\t+\t3M2D\n";
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    // Forwards, the edge skips the first three bases of B.
+    let source = GfaLocation::new(0.into(), 0.into());
+    let target = GfaLocation::new(2.into(), 9.into());
+    let path = shortest_path(&graph, source, target).unwrap().unwrap();
+    assert_eq!(path.length().into_usize(), 16);
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 0.into(), 10.into()),
+            PathElement::new(2.into(), 3.into(), 9.into()),
+        ],
+    );
+
+    // Backwards, the edge skips the first five bases of the reverse of A.
+    let path = shortest_path(&graph, target.invert(&graph), source.invert(&graph))
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.length().into_usize(), 14);
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(3.into(), 1.into(), 10.into()),
+            PathElement::new(1.into(), 5.into(), 10.into()),
+        ],
+    );
+
+    // The path is found backwards, but reported forwards with the forwards length.
+    let path = strand_agnostic_shortest_path(&graph, target, source)
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.combination(), StrandCombination::TargetToSource);
+    assert_eq!(path.path().length().into_usize(), 16);
+    assert_eq!(
+        path.path().iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 0.into(), 10.into()),
+            PathElement::new(2.into(), 3.into(), 9.into()),
+        ],
+    );
+}
+
+#[test]
+fn test_unknown_overlap() {
+    let gfa = "S\tA\tACGT\nS\tB\tACGT\nL\tA\t+\tB\t+\t*\n";
+    let result = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(
+        result,
+        Err(GfaLengthReadError::UnknownOverlap { .. })
+    ));
+
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::AssumeZero,
+    )
+    .unwrap();
+    assert_eq!(
+        graph
+            .iter_successors(0.into())
+            .next()
+            .unwrap()
+            .head_overlap()
+            .into_usize(),
+        0
+    );
+}
+
+#[test]
+fn test_jump_gap() {
+    let gfa = "H\tVN:Z:1.2\nS\tA\t*\tLN:i:10\nS\tB\t*\tLN:i:10\nJ\tA\t+\tB\t+\t5\n";
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    let source = GfaLocation::new(0.into(), 0.into());
+    let target = GfaLocation::new(2.into(), 9.into());
+    let expected_items = [
+        PathItem::Node(PathElement::new(0.into(), 0.into(), 10.into())),
+        PathItem::Gap(GfaPathLength::from_usize(5)),
+        PathItem::Node(PathElement::new(2.into(), 0.into(), 9.into())),
+    ];
+    let path = shortest_path(&graph, source, target).unwrap().unwrap();
+    assert_eq!(path.length().into_usize(), 24);
+    assert_eq!(path.iter_items().collect::<Vec<_>>(), expected_items);
+    assert_eq!(path.iter().count(), 2);
+
+    // The gap is also crossed backwards, and inverted back with the gap in place.
+    let path = strand_agnostic_shortest_path(&graph, target, source)
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.combination(), StrandCombination::TargetToSource);
+    assert_eq!(path.path().length().into_usize(), 24);
+    assert_eq!(path.path().iter_items().collect::<Vec<_>>(), expected_items);
+}
+
+#[test]
+fn test_unknown_jump_gap() {
+    let gfa = "S\tA\tACGT\nS\tB\tACGT\nJ\tA\t+\tB\t+\t*\n";
+    let result = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(result, Err(GfaLengthReadError::UnknownGap { .. })));
+
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::AssumeZero,
+    )
+    .unwrap();
+    let path = shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 0.into()),
+        GfaLocation::new(2.into(), 4.into()),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 8);
+    assert_eq!(path.iter_items().nth(1), Some(PathItem::Gap(0.into())));
+}
+
+#[test]
+fn test_gfa2_gap() {
+    let gfa = "H\tVN:Z:2.0\nS\tA\t10\t*\nS\tB\t8\tACGTACGT\nG\tg1\tA+\tB-\t100\t*\n";
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    assert_eq!(graph.node_length(0.into()).into_usize(), 10);
+    assert_eq!(graph.node_length(2.into()).into_usize(), 8);
+
+    let path = shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 0.into()),
+        GfaLocation::new(3.into(), 8.into()),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 118);
+    assert_eq!(
+        path.iter_items().collect::<Vec<_>>(),
+        [
+            PathItem::Node(PathElement::new(0.into(), 0.into(), 10.into())),
+            PathItem::Gap(100.into()),
+            PathItem::Node(PathElement::new(3.into(), 0.into(), 8.into())),
+        ],
+    );
+
+    let gfa = "H\tVN:Z:2.0\nS\tA\t10\t*\nS\tB\t8\t*\nG\tg1\tA+\tB-\t-3\t*\n";
+    let result = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(
+        result,
+        Err(GfaLengthReadError::MalformedGapDistance(distance)) if distance == "-3"
+    ));
+}
+
+#[test]
+fn test_gfa2_edges() {
+    // The end of A overlaps the end of B, so the edge leads into the reverse of B.
+    let gfa = "H\tVN:Z:2.0\nS\tA\t10\t*\nS\tB\t8\t*\nE\te1\tA+\tB-\t7\t10$\t5\t8$\t3M\n";
+    let graph = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+
+    let path = shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 0.into()),
+        GfaLocation::new(3.into(), 8.into()),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 15);
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 0.into(), 10.into()),
+            PathElement::new(3.into(), 3.into(), 8.into()),
+        ],
+    );
+
+    // An alignment in the middle of A cannot be represented as an edge.
+    let gfa = "H\tVN:Z:2.0\nS\tA\t10\t*\nS\tB\t8\t*\nE\te1\tA+\tB+\t2\t5\t0\t3\t3M\n";
+    let result = read_gfa1_lengths::<u16>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    );
+    assert!(matches!(
+        result,
+        Err(GfaLengthReadError::NonDovetailEdge(edge)) if edge == "e1"
+    ));
+}
@@ -1,6 +1,30 @@
+pub mod annotation;
+pub mod block_cut_tree;
+pub mod bubble;
+pub mod components;
+pub mod constrained;
+pub mod contraction_hierarchy;
+pub mod decomposition;
 pub mod dijkstra;
+pub mod dot;
+pub mod dynamic;
+pub mod haplotype;
+mod heap_size;
+pub mod hub_labels;
+pub mod io;
 pub mod location;
+pub mod overlap;
 pub mod path;
+pub mod reachability;
+pub mod statistics;
+pub mod subgraph;
+pub mod waypoint;
+
+/// Abstraction over the graph representation.
+pub mod graph;
 
 /// Extension traits for GFA graphs.
 pub mod gfa_graph_extensions;
+
+#[cfg(test)]
+mod test_util;
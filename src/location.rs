@@ -1,22 +1,35 @@
-use bidirected_adjacency_array::{
-    graph::BidirectedAdjacencyArray,
-    index::{DirectedNodeIndex, GraphIndexInteger},
-    io::gfa1::GfaNodeData,
-};
+use std::collections::HashSet;
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger};
 use optional_numeric_index::implement_generic_index;
 
-use crate::gfa_graph_extensions::GfaNodeDataExt;
+use crate::{graph::SequenceGraph, path::PathElement};
+
+#[cfg(test)]
+mod tests;
 
 implement_generic_index!(pub GfaNodeOffset, pub OptionalGfaNodeOffset);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GfaLocation<IndexType, LengthType> {
+    node: DirectedNodeIndex<IndexType>,
+    offset: GfaNodeOffset<LengthType>,
+}
+
+/// A range of locations within a directed node.
+///
+/// The range contains all locations with offsets from `offset` to `limit`, both inclusive.
 #[derive(Debug, Clone, Copy)]
-pub struct GfaLocation<IndexType> {
+pub struct GfaLocationRange<IndexType, LengthType> {
     node: DirectedNodeIndex<IndexType>,
-    offset: GfaNodeOffset<IndexType>,
+    offset: GfaNodeOffset<LengthType>,
+    limit: GfaNodeOffset<LengthType>,
 }
 
-impl<IndexType: GraphIndexInteger> GfaLocation<IndexType> {
-    pub fn new(node: DirectedNodeIndex<IndexType>, offset: GfaNodeOffset<IndexType>) -> Self {
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    GfaLocation<IndexType, LengthType>
+{
+    pub fn new(node: DirectedNodeIndex<IndexType>, offset: GfaNodeOffset<LengthType>) -> Self {
         Self { node, offset }
     }
 
@@ -24,23 +37,142 @@ impl<IndexType: GraphIndexInteger> GfaLocation<IndexType> {
         self.node
     }
 
-    pub fn offset(&self) -> GfaNodeOffset<IndexType> {
+    pub fn offset(&self) -> GfaNodeOffset<LengthType> {
         self.offset
     }
 
-    pub fn invert<EdgeData>(
+    pub fn invert(self, graph: &impl SequenceGraph<IndexType, LengthType = LengthType>) -> Self {
+        Self {
+            node: graph.invert(self.node),
+            offset: graph.node_length(self.node) - self.offset,
+        }
+    }
+
+    /// Returns all locations that denote the same sequence position as this location, including itself.
+    ///
+    /// A location inside the overlap of an edge also exists on the other side of the edge,
+    /// and the end of a node is the same position as the start of its successors.
+    /// Equivalence is transitive, so the locations may span several edges, for example if a node is covered entirely by overlaps.
+    /// Asymmetric overlaps are ignored, since the positions inside them cannot be matched without their alignment.
+    /// The locations are sorted by node and offset.
+    pub fn equivalent_locations(
         self,
-        graph: &BidirectedAdjacencyArray<IndexType, impl GfaNodeData, EdgeData>,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> Vec<Self> {
+        let mut locations = vec![self];
+        let mut visited = HashSet::from([self]);
+        let mut index = 0;
+        while let Some(location) = locations.get(index).copied() {
+            // Edges entering the node are found as the edges leaving its inverse.
+            let inverted = location.invert(graph);
+            let mapped: Vec<_> = location
+                .iter_successor_equivalents(graph)
+                .chain(
+                    inverted
+                        .iter_successor_equivalents(graph)
+                        .map(|location| location.invert(graph)),
+                )
+                .collect();
+            for location in mapped {
+                if visited.insert(location) {
+                    locations.push(location);
+                }
+            }
+            index += 1;
+        }
+
+        locations.sort_by_key(|location| (location.node, location.offset));
+        locations
+    }
+
+    /// Returns the unique representative of the locations that are equivalent to this location.
+    ///
+    /// This is the first location returned by [`equivalent_locations`](Self::equivalent_locations).
+    pub fn canonical(self, graph: &impl SequenceGraph<IndexType, LengthType = LengthType>) -> Self {
+        self.equivalent_locations(graph)[0]
+    }
+
+    /// Map the location into each successor whose overlap contains it.
+    fn iter_successor_equivalents(
+        self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> impl Iterator<Item = Self> {
+        let node_length = graph.node_length(self.node);
+        graph
+            .iter_successors(self.node)
+            .filter_map(move |successor| {
+                let overlap = successor.head_overlap();
+                let is_mappable = successor.gap().is_none()
+                    && successor.tail_overlap() == overlap
+                    && overlap <= node_length
+                    && overlap <= graph.node_length(successor.node())
+                    && self.offset.into_length() + overlap >= node_length;
+                is_mappable.then(|| {
+                    Self::new(
+                        successor.node(),
+                        (self.offset.into_length() + overlap - node_length).into_offset(),
+                    )
+                })
+            })
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    GfaLocationRange<IndexType, LengthType>
+{
+    pub fn new(
+        node: DirectedNodeIndex<IndexType>,
+        offset: GfaNodeOffset<LengthType>,
+        limit: GfaNodeOffset<LengthType>,
     ) -> Self {
+        assert!(offset <= limit);
         Self {
-            node: self.node.invert(),
-            offset: graph.node_data(self.node.into_bidirected()).len() - self.offset,
+            node,
+            offset,
+            limit,
         }
     }
+
+    pub fn node(&self) -> DirectedNodeIndex<IndexType> {
+        self.node
+    }
+
+    pub fn offset(&self) -> GfaNodeOffset<LengthType> {
+        self.offset
+    }
+
+    pub fn limit(&self) -> GfaNodeOffset<LengthType> {
+        self.limit
+    }
+
+    pub fn invert(self, graph: &impl SequenceGraph<IndexType, LengthType = LengthType>) -> Self {
+        let node_length = graph.node_length(self.node);
+        Self {
+            node: graph.invert(self.node),
+            offset: node_length - self.limit,
+            limit: node_length - self.offset,
+        }
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    From<GfaLocation<IndexType, LengthType>> for GfaLocationRange<IndexType, LengthType>
+{
+    fn from(location: GfaLocation<IndexType, LengthType>) -> Self {
+        Self::new(location.node, location.offset, location.offset)
+    }
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    From<PathElement<IndexType, LengthType>> for GfaLocationRange<IndexType, LengthType>
+{
+    fn from(element: PathElement<IndexType, LengthType>) -> Self {
+        Self::new(element.node(), element.offset(), element.limit())
+    }
 }
 
-impl<IndexType: GraphIndexInteger> GfaNodeOffset<IndexType> {
-    pub fn into_length(self) -> crate::path::GfaPathLength<IndexType> {
+impl<LengthType: GraphIndexInteger> GfaNodeOffset<LengthType> {
+    pub fn into_length(self) -> crate::path::GfaPathLength<LengthType> {
         crate::path::GfaPathLength::from_raw(self.into_raw())
     }
 }
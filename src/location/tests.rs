@@ -0,0 +1,82 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::PlainGfaNodeData,
+};
+
+use crate::{
+    dijkstra::{equivalence_aware_shortest_path, shortest_path},
+    location::GfaLocation,
+};
+
+#[test]
+fn test_equivalent_locations() {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+        PlainGfaNodeData::new("C", "CC"),
+    ];
+    let edges = [(0, 2, 2), (2, 4, 0)].map(|(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    let graph =
+        BidirectedAdjacencyArray::<u8, _, _>::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let location = GfaLocation::new(2.into(), 1.into());
+    assert_eq!(
+        location.equivalent_locations(&graph),
+        [
+            GfaLocation::new(0.into(), 3.into()),
+            GfaLocation::new(2.into(), 1.into()),
+        ],
+    );
+    assert_eq!(
+        location.canonical(&graph),
+        GfaLocation::new(0.into(), 3.into())
+    );
+    assert_eq!(
+        GfaLocation::new(0.into(), 3.into()).canonical(&graph),
+        location.canonical(&graph),
+    );
+
+    // The end of B is the start of C.
+    assert_eq!(
+        GfaLocation::new(4.into(), 0.into()).equivalent_locations(&graph),
+        [
+            GfaLocation::new(2.into(), 3.into()),
+            GfaLocation::new(4.into(), 0.into()),
+        ],
+    );
+
+    // Outside of overlaps, a location is only equivalent to itself.
+    let location = GfaLocation::new(0.into(), 1.into());
+    assert_eq!(location.equivalent_locations(&graph), [location]);
+
+    // Equivalent locations have distance zero, even though the path between them would go backwards.
+    let source = GfaLocation::new(2.into(), 1.into());
+    let target = GfaLocation::new(0.into(), 3.into());
+    assert!(shortest_path(&graph, source, target).unwrap().is_none());
+    assert_eq!(
+        equivalence_aware_shortest_path(&graph, source, target)
+            .unwrap()
+            .unwrap()
+            .length()
+            .into_usize(),
+        0,
+    );
+
+    let target = GfaLocation::new(0.into(), 4.into());
+    assert!(shortest_path(&graph, source, target).unwrap().is_none());
+    assert_eq!(
+        equivalence_aware_shortest_path(&graph, source, target)
+            .unwrap()
+            .unwrap()
+            .length()
+            .into_usize(),
+        1,
+    );
+}
@@ -0,0 +1,319 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger};
+
+use crate::{
+    dijkstra::shortest_path,
+    graph::{ClampedOverlaps, SequenceGraph, Successor},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// An edge whose head overlap exceeds the length of the node it enters.
+///
+/// Traversing such an edge moves the path backwards, i.e. the node contributes a negative length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlongOverlap<IndexType, LengthType> {
+    from: DirectedNodeIndex<IndexType>,
+    to: DirectedNodeIndex<IndexType>,
+    overlap: GfaPathLength<LengthType>,
+    node_length: GfaPathLength<LengthType>,
+}
+
+/// How to handle edges whose overlaps exceed the lengths of their endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverlongOverlapPolicy {
+    /// Fail with [`OverlapError::OverlongOverlap`] if the graph contains an overlong overlap.
+    Error,
+
+    /// Clamp each overlap to the lengths of the nodes it consumes, see [`ClampedOverlaps`].
+    Clamp,
+
+    /// Let nodes contribute negative lengths.
+    ///
+    /// If the graph contains an overlong overlap, the search falls back to a Bellman-Ford-style search,
+    /// which is much slower than the default search.
+    NegativeCosts,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum OverlapError {
+    #[error(
+        "the edge from {from} to {to} has an overlap of {overlap}, which exceeds the length {node_length} of {to}"
+    )]
+    OverlongOverlap {
+        from: usize,
+        to: usize,
+        overlap: usize,
+        node_length: usize,
+    },
+
+    #[error("a cycle of negative length can be used to reach the target")]
+    NegativeCycle,
+
+    #[error(transparent)]
+    LengthOverflow(#[from] LengthOverflowError),
+}
+
+/// A label of the negative-cost search, which is never modified after creation.
+struct Label<IndexType, LengthType> {
+    /// The edge that enters the node of the label.
+    entry: Successor<IndexType, LengthType>,
+    cost: i128,
+    predecessor: Option<usize>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct SearchState<IndexType> {
+    node: DirectedNodeIndex<IndexType>,
+    /// The cost of the state if a continuation from it might still end before the target.
+    unsaturated_cost: Option<i128>,
+}
+
+/// Find all edges whose head overlap exceeds the length of the node they enter.
+///
+/// Each directed edge is checked separately, so an edge whose tail overlap exceeds the length of its tail is found via its inverse.
+pub fn find_overlong_overlaps<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+) -> Vec<OverlongOverlap<IndexType, LengthType>> {
+    iter_directed_nodes(graph)
+        .flat_map(|from| {
+            graph.iter_successors(from).filter_map(move |successor| {
+                let node_length = graph.node_length(successor.node());
+                (successor.gap().is_none() && successor.head_overlap() > node_length).then_some(
+                    OverlongOverlap {
+                        from,
+                        to: successor.node(),
+                        overlap: successor.head_overlap(),
+                        node_length,
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+/// Find the shortest path from `source` to `target`, handling overlong overlaps as specified by `policy`.
+///
+/// If the graph contains no overlong overlaps, this is equivalent to [`shortest_path`].
+pub fn checked_shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: GfaLocation<IndexType, LengthType>,
+    target: GfaLocation<IndexType, LengthType>,
+    policy: OverlongOverlapPolicy,
+) -> Result<Option<GfaPath<IndexType, LengthType>>, OverlapError> {
+    if policy == OverlongOverlapPolicy::Clamp {
+        return Ok(shortest_path(&ClampedOverlaps::new(graph), source, target)?);
+    }
+
+    match (find_overlong_overlaps(graph).first(), policy) {
+        (None, _) => Ok(shortest_path(graph, source, target)?),
+        (Some(overlong_overlap), OverlongOverlapPolicy::Error) => {
+            Err(OverlapError::OverlongOverlap {
+                from: overlong_overlap.from.into_usize(),
+                to: overlong_overlap.to.into_usize(),
+                overlap: overlong_overlap.overlap.into_usize(),
+                node_length: overlong_overlap.node_length.into_usize(),
+            })
+        }
+        (Some(_), _) => negative_cost_shortest_path(graph, source, target),
+    }
+}
+
+/// Find the shortest path from `source` to `target` in a graph where nodes may contribute negative lengths.
+///
+/// First, the minimum length of a continuation from each node reachable from the source to the target is computed with the Bellman-Ford algorithm,
+/// which also detects negative cycles.
+/// Negative cycles that cannot be reached from the source do not affect the result, so they are ignored.
+/// Then, a label-correcting search from the source merges labels at a node only if no continuation from them can end before the target.
+/// Costs are measured from the source to the end of the node of a label, as in [`shortest_path`].
+fn negative_cost_shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    source: GfaLocation<IndexType, LengthType>,
+    target: GfaLocation<IndexType, LengthType>,
+) -> Result<Option<GfaPath<IndexType, LengthType>>, OverlapError> {
+    let weight = |successor: &Successor<IndexType, LengthType>| {
+        successor.gap().map_or(0, signed) + signed(graph.node_length(successor.node()))
+            - signed(successor.head_overlap())
+    };
+
+    // The minimum length of a walk from the end of each reachable node to the end of the target node.
+    // Successors of reachable nodes are reachable, so the rounds only need to relax the edges leaving reachable nodes.
+    // A change in the last round means that a negative cycle can be used to reach the target.
+    let reachable_nodes = reachable_nodes(graph, source.node());
+    let mut continuation = vec![None; 2 * graph.node_count()];
+    continuation[target.node().into_usize()] = Some(0);
+    for round in 0..reachable_nodes.len() {
+        let mut is_changed = false;
+        for node in reachable_nodes.iter().copied() {
+            for successor in graph.iter_successors(node) {
+                if let Some(successor_continuation) = continuation[successor.node().into_usize()] {
+                    let candidate = weight(&successor) + successor_continuation;
+                    if continuation[node.into_usize()].is_none_or(|current| candidate < current) {
+                        continuation[node.into_usize()] = Some(candidate);
+                        is_changed = true;
+                    }
+                }
+            }
+        }
+
+        if !is_changed {
+            break;
+        } else if round + 1 == reachable_nodes.len() {
+            return Err(OverlapError::NegativeCycle);
+        }
+    }
+
+    // A label reaches the target if its cost is at least this threshold.
+    // Labels whose cost cannot drop below the threshold anymore are merged, keeping the cheapest.
+    let target_threshold =
+        signed(graph.node_length(target.node())) - signed(target.offset().into_length());
+    let state = |node: DirectedNodeIndex<IndexType>, cost: i128| {
+        let saturation_threshold = target_threshold - continuation[node.into_usize()].unwrap();
+        SearchState {
+            node,
+            unsaturated_cost: (cost < saturation_threshold).then_some(cost),
+        }
+    };
+
+    let mut labels = Vec::new();
+    let mut queue = VecDeque::new();
+    let mut saturated_states = HashMap::new();
+    let mut unsaturated_states = HashSet::new();
+    let mut best_target: Option<(i128, usize)> = None;
+
+    if continuation[source.node().into_usize()].is_some() {
+        let cost = signed(graph.node_length(source.node())) - signed(source.offset().into_length());
+        let source_state = state(source.node(), cost);
+        if source_state.unsaturated_cost.is_some() {
+            unsaturated_states.insert(source_state);
+        } else {
+            saturated_states.insert(source.node(), 0);
+        }
+        queue.push_back(0);
+        labels.push(Label {
+            entry: Successor::new(source.node(), GfaPathLength::from_usize(0)),
+            cost,
+            predecessor: None,
+        });
+    }
+
+    while let Some(label_index) = queue.pop_front() {
+        let Label { entry, cost, .. } = labels[label_index];
+        let node = entry.node();
+        if state(node, cost).unsaturated_cost.is_none() && saturated_states[&node] != label_index {
+            // Superseded by a cheaper label.
+            continue;
+        }
+
+        if node == target.node()
+            && cost >= target_threshold
+            && best_target.is_none_or(|(distance, _)| cost - target_threshold < distance)
+        {
+            best_target = Some((cost - target_threshold, label_index));
+        }
+
+        for successor in graph.iter_successors(node) {
+            if continuation[successor.node().into_usize()].is_none() {
+                continue;
+            }
+
+            let successor_cost = cost + weight(&successor);
+            let successor_state = state(successor.node(), successor_cost);
+            if successor_state.unsaturated_cost.is_some() {
+                if !unsaturated_states.insert(successor_state) {
+                    continue;
+                }
+            } else if let Some(&current) = saturated_states.get(&successor.node())
+                && labels[current].cost <= successor_cost
+            {
+                continue;
+            } else {
+                saturated_states.insert(successor.node(), labels.len());
+            }
+
+            queue.push_back(labels.len());
+            labels.push(Label {
+                entry: successor,
+                cost: successor_cost,
+                predecessor: Some(label_index),
+            });
+        }
+    }
+
+    let Some((distance, label_index)) = best_target else {
+        return Ok(None);
+    };
+
+    let mut walk = Vec::new();
+    let mut current = Some(label_index);
+    while let Some(label_index) = current {
+        walk.push(labels[label_index].entry);
+        current = labels[label_index].predecessor;
+    }
+    walk.reverse();
+
+    Ok(Some(GfaPath::from_overlong_walk(
+        walk,
+        source.offset(),
+        // Targets are only reached at costs above their threshold, so the distance is never negative.
+        GfaPathLength::checked_from_usize(distance as usize)?,
+        graph,
+    )))
+}
+
+/// Returns the directed nodes that can be reached from `source`, including `source` itself.
+fn reachable_nodes<IndexType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType>,
+    source: DirectedNodeIndex<IndexType>,
+) -> Vec<DirectedNodeIndex<IndexType>> {
+    let mut is_reached = vec![false; 2 * graph.node_count()];
+    is_reached[source.into_usize()] = true;
+    let mut reachable_nodes = vec![source];
+    let mut index = 0;
+    while let Some(node) = reachable_nodes.get(index).copied() {
+        for successor in graph.iter_successors(node) {
+            if !is_reached[successor.node().into_usize()] {
+                is_reached[successor.node().into_usize()] = true;
+                reachable_nodes.push(successor.node());
+            }
+        }
+        index += 1;
+    }
+    reachable_nodes
+}
+
+fn iter_directed_nodes<IndexType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType>,
+) -> impl Iterator<Item = DirectedNodeIndex<IndexType>> {
+    (0..2 * graph.node_count()).map(DirectedNodeIndex::from_usize)
+}
+
+fn signed<LengthType: GraphIndexInteger>(length: GfaPathLength<LengthType>) -> i128 {
+    length.into_usize() as i128
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    OverlongOverlap<IndexType, LengthType>
+{
+    pub fn from(&self) -> DirectedNodeIndex<IndexType> {
+        self.from
+    }
+
+    pub fn to(&self) -> DirectedNodeIndex<IndexType> {
+        self.to
+    }
+
+    /// The head overlap of the edge.
+    pub fn overlap(&self) -> GfaPathLength<LengthType> {
+        self.overlap
+    }
+
+    /// The length of the node the edge enters.
+    pub fn node_length(&self) -> GfaPathLength<LengthType> {
+        self.node_length
+    }
+}
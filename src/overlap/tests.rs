@@ -0,0 +1,170 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    location::GfaLocation,
+    overlap::{OverlapError, OverlongOverlapPolicy, checked_shortest_path, find_overlong_overlaps},
+    path::PathElement,
+};
+
+fn graph(
+    nodes: Vec<PlainGfaNodeData>,
+    edges: &[(u8, u8, u16)],
+) -> BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> {
+    let edges = edges.iter().map(|&(from, to, overlap)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(from),
+            DirectedNodeIndex::new(to),
+            overlap,
+        )
+    });
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+#[test]
+fn test_overlong_overlap_policies() {
+    // The overlap from A to B covers B and one more base.
+    let graph = graph(
+        vec![
+            PlainGfaNodeData::new("A", "AAAA"),
+            PlainGfaNodeData::new("B", "BB"),
+            PlainGfaNodeData::new("C", "CCCC"),
+        ],
+        &[(0, 2, 3), (2, 4, 1)],
+    );
+    let overlong_overlaps = find_overlong_overlaps(&graph);
+    assert_eq!(overlong_overlaps.len(), 1);
+    assert_eq!(overlong_overlaps[0].from(), 0.into());
+    assert_eq!(overlong_overlaps[0].to(), 2.into());
+
+    let source = GfaLocation::new(0.into(), 0.into());
+    let target = GfaLocation::new(4.into(), 4.into());
+    assert_eq!(
+        checked_shortest_path(&graph, source, target, OverlongOverlapPolicy::Error).unwrap_err(),
+        OverlapError::OverlongOverlap {
+            from: 0,
+            to: 2,
+            overlap: 3,
+            node_length: 2,
+        },
+    );
+
+    let path = checked_shortest_path(&graph, source, target, OverlongOverlapPolicy::Clamp)
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.length().into_usize(), 7);
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 0.into(), 4.into()),
+            PathElement::new(2.into(), 2.into(), 2.into()),
+            PathElement::new(4.into(), 1.into(), 4.into()),
+        ],
+    );
+
+    // B moves the path back by one base, so the walk enters C after its first two bases.
+    let path = checked_shortest_path(&graph, source, target, OverlongOverlapPolicy::NegativeCosts)
+        .unwrap()
+        .unwrap();
+    assert_eq!(path.length().into_usize(), 6);
+    assert_eq!(
+        path.iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 0.into(), 4.into()),
+            PathElement::new(2.into(), 2.into(), 2.into()),
+            PathElement::new(4.into(), 2.into(), 4.into()),
+        ],
+    );
+}
+
+#[test]
+fn test_negative_costs_without_overlong_overlaps() {
+    let graph = graph(
+        vec![
+            PlainGfaNodeData::new("A", "AAA"),
+            PlainGfaNodeData::new("B", "BBB"),
+        ],
+        &[(0, 2, 1)],
+    );
+    assert!(find_overlong_overlaps(&graph).is_empty());
+
+    let path = checked_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 1.into()),
+        GfaLocation::new(2.into(), 3.into()),
+        OverlongOverlapPolicy::NegativeCosts,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 4);
+}
+
+#[test]
+fn test_negative_cycle() {
+    // Each traversal of the self loop moves the path back by one base.
+    let graph = graph(vec![PlainGfaNodeData::new("A", "AA")], &[(0, 0, 3)]);
+
+    let result = checked_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 0.into()),
+        GfaLocation::new(0.into(), 2.into()),
+        OverlongOverlapPolicy::NegativeCosts,
+    );
+    assert_eq!(result.unwrap_err(), OverlapError::NegativeCycle);
+}
+
+#[test]
+fn test_unreachable_negative_cycle() {
+    // C and D form a negative cycle that reaches B, but it cannot be reached from A.
+    let graph = graph(
+        vec![
+            PlainGfaNodeData::new("A", "AAAA"),
+            PlainGfaNodeData::new("B", "BBBB"),
+            PlainGfaNodeData::new("C", "CC"),
+            PlainGfaNodeData::new("D", "DD"),
+        ],
+        &[(0, 2, 1), (4, 6, 3), (6, 4, 3), (4, 2, 0)],
+    );
+
+    let path = checked_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 0.into()),
+        GfaLocation::new(2.into(), 4.into()),
+        OverlongOverlapPolicy::NegativeCosts,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(path.length().into_usize(), 7);
+
+    // From C, the cycle can be used to reach B.
+    let result = checked_shortest_path(
+        &graph,
+        GfaLocation::new(4.into(), 0.into()),
+        GfaLocation::new(2.into(), 4.into()),
+        OverlongOverlapPolicy::NegativeCosts,
+    );
+    assert_eq!(result.unwrap_err(), OverlapError::NegativeCycle);
+}
+
+#[test]
+fn test_target_behind_source() {
+    // Entering B moves the path back by two bases, so B ends before the source and the target is never reached.
+    let graph = graph(
+        vec![
+            PlainGfaNodeData::new("A", "AAAA"),
+            PlainGfaNodeData::new("B", "B"),
+        ],
+        &[(0, 2, 3)],
+    );
+
+    let result = checked_shortest_path(
+        &graph,
+        GfaLocation::new(0.into(), 3.into()),
+        GfaLocation::new(2.into(), 1.into()),
+        OverlongOverlapPolicy::NegativeCosts,
+    );
+    assert!(result.unwrap().is_none());
+}
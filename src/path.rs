@@ -1,56 +1,269 @@
 use std::{
     fmt::Debug,
+    iter::Peekable,
     ops::{Add, AddAssign, Sub, SubAssign},
 };
 
-use bidirected_adjacency_array::{
-    graph::BidirectedAdjacencyArray,
-    index::{DirectedNodeIndex, GraphIndexInteger},
-    io::gfa1::GfaNodeData,
-};
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger};
 use optional_numeric_index::implement_generic_index;
 
-use crate::{gfa_graph_extensions::GfaNodeDataExt, location::GfaNodeOffset};
+use crate::{
+    graph::{SequenceGraph, Successor},
+    location::GfaNodeOffset,
+};
+
+#[cfg(test)]
+mod tests;
 
 implement_generic_index!(pub GfaPathLength, pub OptionalGfaPathLength);
 
-pub struct GfaPath<IndexType> {
-    path: Vec<PathElement<IndexType>>,
-    length: GfaPathLength<IndexType>,
+/// A path through a graph.
+///
+/// Node indices are of type `IndexType`, while offsets and lengths are of type `LengthType`.
+/// This allows paths that are longer than the number of nodes in the graph.
+/// Paths that cross gap edges contain [`PathItem::Gap`] items between the node elements.
+#[derive(Clone)]
+pub struct GfaPath<IndexType, LengthType> {
+    path: Vec<PathItem<IndexType, LengthType>>,
+    length: GfaPathLength<LengthType>,
 }
 
+/// An item of a path, which is either a section of a node or a gap between two nodes.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub struct PathElement<IndexType> {
+pub enum PathItem<IndexType, LengthType> {
+    Node(PathElement<IndexType, LengthType>),
+    /// A gap of the given length, spanned by a gap edge.
+    Gap(GfaPathLength<LengthType>),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PathElement<IndexType, LengthType> {
     node: DirectedNodeIndex<IndexType>,
-    offset: GfaNodeOffset<IndexType>,
-    limit: GfaNodeOffset<IndexType>,
+    offset: GfaNodeOffset<LengthType>,
+    limit: GfaNodeOffset<LengthType>,
+}
+
+/// The items of the path along a walk, computed one node of the walk at a time.
+///
+/// The items are laid out as described for [`GfaPath::from_walk`].
+/// Positions are signed, since walks over overlong overlaps may leave the bounds of a node.
+pub(crate) struct WalkItems<'graph, Walk: Iterator, Graph, IndexType, LengthType> {
+    walk: Peekable<Walk>,
+    graph: &'graph Graph,
+    /// The position of the path in the previous node, or the source offset before the first node.
+    position: i128,
+    remaining_length: i128,
+    previous_node_length: Option<i128>,
+    /// If set, elements of nodes that the walk passes outside of are clamped to the node instead of panicking.
+    is_overlong: bool,
+    /// The element of a node that is entered over a gap, returned after the gap.
+    pending_element: Option<PathElement<IndexType, LengthType>>,
 }
 
-impl<IndexType: GraphIndexInteger> GfaPath<IndexType> {
-    pub fn new(path: Vec<PathElement<IndexType>>, length: GfaPathLength<IndexType>) -> Self {
+/// The error returned by checked arithmetic on offsets and lengths.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("length arithmetic overflowed the length type")]
+pub struct LengthOverflowError;
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> GfaPath<IndexType, LengthType> {
+    pub fn new(
+        path: Vec<PathElement<IndexType, LengthType>>,
+        length: GfaPathLength<LengthType>,
+    ) -> Self {
+        Self::from_items(path.into_iter().map(PathItem::Node).collect(), length)
+    }
+
+    pub fn from_items(
+        path: Vec<PathItem<IndexType, LengthType>>,
+        length: GfaPathLength<LengthType>,
+    ) -> Self {
         Self { path, length }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = PathElement<IndexType>> {
+    /// Build a path from a walk through the graph.
+    ///
+    /// The walk is given as the edges that enter each of its directed nodes.
+    /// The edge of the first node is ignored.
+    /// The path starts at `source_offset` in the first node and has the given length.
+    ///
+    /// The element of the first node extends as far as possible into its node.
+    /// Each later element does the same, unless it is entered at a position that lies inside the overlap with the next node.
+    /// Then the path continues into the next node immediately, such that walks through nodes that are covered by their overlaps
+    /// attribute no length to these nodes.
+    ///
+    /// Panics if the walk leaves the bounds of a node, which requires overlong overlaps,
+    /// see [`from_overlong_walk`](Self::from_overlong_walk).
+    pub(crate) fn from_walk(
+        walk: impl IntoIterator<Item = Successor<IndexType, LengthType>>,
+        source_offset: GfaNodeOffset<LengthType>,
+        length: GfaPathLength<LengthType>,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> Self {
+        let path = WalkItems::new(walk.into_iter(), source_offset, length, graph).collect();
+        Self { path, length }
+    }
+
+    /// Build a path from a walk that may use overlaps which exceed the lengths of the nodes they enter.
+    ///
+    /// The layout is the same as for [`from_walk`](Self::from_walk).
+    /// If the walk is positioned outside of a node, the element of the node is empty at the nearest end of the node.
+    pub(crate) fn from_overlong_walk(
+        walk: impl IntoIterator<Item = Successor<IndexType, LengthType>>,
+        source_offset: GfaNodeOffset<LengthType>,
+        length: GfaPathLength<LengthType>,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    ) -> Self {
+        let mut items = WalkItems::new(walk.into_iter(), source_offset, length, graph);
+        items.is_overlong = true;
+        let path = items.collect();
+        Self { path, length }
+    }
+
+    /// Iterate over the node elements of the path, skipping gaps.
+    pub fn iter(&self) -> impl Iterator<Item = PathElement<IndexType, LengthType>> {
+        self.path.iter().filter_map(|item| match item {
+            PathItem::Node(element) => Some(*element),
+            PathItem::Gap(_) => None,
+        })
+    }
+
+    /// Iterate over the node elements and the gaps of the path.
+    pub fn iter_items(&self) -> impl Iterator<Item = PathItem<IndexType, LengthType>> {
         self.path.iter().copied()
     }
 
-    pub fn length(&self) -> GfaPathLength<IndexType> {
+    pub fn length(&self) -> GfaPathLength<LengthType> {
         self.length
     }
 }
 
-impl<IndexType: GraphIndexInteger> GfaPathLength<IndexType> {
-    pub fn into_offset(self) -> GfaNodeOffset<IndexType> {
+impl<'graph, Walk: Iterator, Graph, IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    WalkItems<'graph, Walk, Graph, IndexType, LengthType>
+{
+    pub(crate) fn new(
+        walk: Walk,
+        source_offset: GfaNodeOffset<LengthType>,
+        length: GfaPathLength<LengthType>,
+        graph: &'graph Graph,
+    ) -> Self {
+        Self {
+            walk: walk.peekable(),
+            graph,
+            position: signed(source_offset.into_length()),
+            remaining_length: signed(length),
+            previous_node_length: None,
+            is_overlong: false,
+            pending_element: None,
+        }
+    }
+}
+
+impl<
+    Walk: Iterator<Item = Successor<IndexType, LengthType>>,
+    Graph: SequenceGraph<IndexType, LengthType = LengthType>,
+    IndexType: GraphIndexInteger,
+    LengthType: GraphIndexInteger,
+> Iterator for WalkItems<'_, Walk, Graph, IndexType, LengthType>
+{
+    type Item = PathItem<IndexType, LengthType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(element) = self.pending_element.take() {
+            return Some(PathItem::Node(element));
+        }
+        let Some(entry) = self.walk.next() else {
+            debug_assert_eq!(
+                self.remaining_length, 0,
+                "Walk is too short for the path length.",
+            );
+            return None;
+        };
+
+        let node = entry.node();
+        let node_length = signed(self.graph.node_length(node));
+        let mut gap = None;
+        if let Some(previous_node_length) = self.previous_node_length {
+            if let Some(entry_gap) = entry.gap() {
+                gap = Some(entry_gap);
+                self.remaining_length -= signed(entry_gap);
+            }
+
+            // Move the position from the previous node into this node.
+            self.position += signed(entry.head_overlap()) - previous_node_length;
+        }
+
+        let offset = self.position;
+        let limit = match self.walk.peek() {
+            None => offset + self.remaining_length,
+            Some(next)
+                if self.previous_node_length.is_some()
+                    && next.gap().is_none()
+                    && offset >= node_length - signed(next.head_overlap()) =>
+            {
+                offset
+            }
+            Some(_) => (offset + self.remaining_length).min(node_length.max(offset)),
+        };
+        self.remaining_length -= limit - offset;
+        assert!(
+            self.is_overlong || (0 <= offset && limit <= node_length),
+            "The walk leaves the bounds of node {node}, which requires an overlong overlap.",
+        );
+
+        let unsigned =
+            |position: i128| GfaNodeOffset::from_usize(position.clamp(0, node_length) as usize);
+        let element = PathElement::new(node, unsigned(offset), unsigned(limit));
+
+        self.position = limit;
+        self.previous_node_length = Some(node_length);
+        if let Some(gap) = gap {
+            self.pending_element = Some(element);
+            Some(PathItem::Gap(gap))
+        } else {
+            Some(PathItem::Node(element))
+        }
+    }
+}
+
+fn signed<LengthType: GraphIndexInteger>(length: GfaPathLength<LengthType>) -> i128 {
+    length.into_usize() as i128
+}
+
+impl<LengthType: GraphIndexInteger> GfaPathLength<LengthType> {
+    pub fn into_offset(self) -> GfaNodeOffset<LengthType> {
         GfaNodeOffset::from_raw(self.into_raw())
     }
+
+    pub fn checked_add(self, rhs: Self) -> Result<Self, LengthOverflowError> {
+        checked_length(self.into_raw().checked_add(&rhs.into_raw()))
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, LengthOverflowError> {
+        checked_length(self.into_raw().checked_sub(&rhs.into_raw()))
+    }
+
+    /// Convert a length, failing if it does not fit into the length type.
+    pub fn checked_from_usize(value: usize) -> Result<Self, LengthOverflowError> {
+        checked_length(LengthType::try_from(value).ok())
+    }
+}
+
+/// Wrap the result of a checked operation, treating the maximum value as overflow since it is reserved for `None`.
+fn checked_length<LengthType: GraphIndexInteger>(
+    raw: Option<LengthType>,
+) -> Result<GfaPathLength<LengthType>, LengthOverflowError> {
+    raw.filter(|raw| *raw != LengthType::max_value())
+        .map(GfaPathLength::from_raw)
+        .ok_or(LengthOverflowError)
 }
 
-impl<IndexType: GraphIndexInteger> PathElement<IndexType> {
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    PathElement<IndexType, LengthType>
+{
     pub fn new(
         node: DirectedNodeIndex<IndexType>,
-        offset: GfaNodeOffset<IndexType>,
-        limit: GfaNodeOffset<IndexType>,
+        offset: GfaNodeOffset<LengthType>,
+        limit: GfaNodeOffset<LengthType>,
     ) -> Self {
         Self {
             node,
@@ -59,16 +272,16 @@ impl<IndexType: GraphIndexInteger> PathElement<IndexType> {
         }
     }
 
-    pub fn new_inverted<EdgeData>(
+    pub fn new_inverted(
         node: DirectedNodeIndex<IndexType>,
-        offset: GfaNodeOffset<IndexType>,
-        limit: GfaNodeOffset<IndexType>,
-        graph: &BidirectedAdjacencyArray<IndexType, impl GfaNodeData, EdgeData>,
+        offset: GfaNodeOffset<LengthType>,
+        limit: GfaNodeOffset<LengthType>,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
     ) -> Self {
         Self {
-            node: node.invert(),
-            offset: graph.node_data(node.into_bidirected()).len() - limit,
-            limit: graph.node_data(node.into_bidirected()).len() - offset,
+            node: graph.invert(node),
+            offset: graph.node_length(node) - limit,
+            limit: graph.node_length(node) - offset,
         }
     }
 
@@ -76,96 +289,93 @@ impl<IndexType: GraphIndexInteger> PathElement<IndexType> {
         self.node
     }
 
-    pub fn offset(&self) -> GfaNodeOffset<IndexType> {
+    pub fn offset(&self) -> GfaNodeOffset<LengthType> {
         self.offset
     }
 
-    pub fn limit(&self) -> GfaNodeOffset<IndexType> {
+    pub fn limit(&self) -> GfaNodeOffset<LengthType> {
         self.limit
     }
 
-    pub fn length(&self) -> GfaPathLength<IndexType> {
+    pub fn length(&self) -> GfaPathLength<LengthType> {
         self.limit - self.offset
     }
-
-    /// Push the path element to the left by the given decrement.
-    ///
-    /// The decrement is subtracted from the limit, and the offset is adjusted to not exceed the new limit.
-    /// The amount of shift applied to the offset is returned.
-    pub(crate) fn decrease_limit(
-        &mut self,
-        decrement: GfaNodeOffset<IndexType>,
-    ) -> GfaNodeOffset<IndexType> {
-        self.limit -= decrement;
-        if self.offset > self.limit {
-            let shift = self.offset - self.limit;
-            self.offset = self.limit;
-            shift.into_offset()
-        } else {
-            GfaNodeOffset::from_usize(0)
-        }
-    }
 }
 
-impl<IndexType: GraphIndexInteger> Sub for GfaNodeOffset<IndexType> {
-    type Output = GfaPathLength<IndexType>;
+// The arithmetic operators panic on overflow in all build profiles, since wrapping lengths would silently yield wrong distances.
+// Use the checked methods to handle overflow gracefully.
+
+impl<LengthType: GraphIndexInteger> Sub for GfaNodeOffset<LengthType> {
+    type Output = GfaPathLength<LengthType>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        GfaPathLength::from_raw(self.into_raw() - rhs.into_raw())
+        self.into_length()
+            .checked_sub(rhs.into_length())
+            .expect("offset subtraction overflowed")
     }
 }
 
-impl<IndexType: GraphIndexInteger> AddAssign for GfaNodeOffset<IndexType> {
+impl<LengthType: GraphIndexInteger> AddAssign for GfaNodeOffset<LengthType> {
     fn add_assign(&mut self, rhs: Self) {
-        *self = GfaNodeOffset::from_raw(self.into_raw() + rhs.into_raw());
+        *self = self
+            .into_length()
+            .checked_add(rhs.into_length())
+            .expect("offset addition overflowed")
+            .into_offset();
     }
 }
 
-impl<IndexType: GraphIndexInteger> SubAssign for GfaNodeOffset<IndexType> {
+impl<LengthType: GraphIndexInteger> SubAssign for GfaNodeOffset<LengthType> {
     fn sub_assign(&mut self, rhs: Self) {
-        *self = GfaNodeOffset::from_raw(self.into_raw() - rhs.into_raw());
+        *self = (*self - rhs).into_offset();
     }
 }
 
-impl<IndexType: GraphIndexInteger> Sub<GfaNodeOffset<IndexType>> for GfaPathLength<IndexType> {
-    type Output = GfaNodeOffset<IndexType>;
+impl<LengthType: GraphIndexInteger> Sub<GfaNodeOffset<LengthType>> for GfaPathLength<LengthType> {
+    type Output = GfaNodeOffset<LengthType>;
 
-    fn sub(self, rhs: GfaNodeOffset<IndexType>) -> Self::Output {
-        GfaNodeOffset::from_raw(self.into_raw() - rhs.into_raw())
+    fn sub(self, rhs: GfaNodeOffset<LengthType>) -> Self::Output {
+        (self - rhs.into_length()).into_offset()
     }
 }
 
-impl<IndexType: GraphIndexInteger> Add for GfaPathLength<IndexType> {
-    type Output = GfaPathLength<IndexType>;
+impl<LengthType: GraphIndexInteger> Add for GfaPathLength<LengthType> {
+    type Output = GfaPathLength<LengthType>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        GfaPathLength::from_raw(self.into_raw() + rhs.into_raw())
+        self.checked_add(rhs).expect("length addition overflowed")
     }
 }
 
-impl<IndexType: GraphIndexInteger> Sub for GfaPathLength<IndexType> {
-    type Output = GfaPathLength<IndexType>;
+impl<LengthType: GraphIndexInteger> Sub for GfaPathLength<LengthType> {
+    type Output = GfaPathLength<LengthType>;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        GfaPathLength::from_raw(self.into_raw() - rhs.into_raw())
+        self.checked_sub(rhs)
+            .expect("length subtraction overflowed")
     }
 }
 
-impl<IndexType: GraphIndexInteger> Debug for GfaPath<IndexType> {
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger> Debug
+    for GfaPath<IndexType, LengthType>
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "GfaPath(length: {}, path: [", self.length)?;
 
-        for (i, element) in self.path.iter().enumerate() {
+        for (i, item) in self.path.iter().enumerate() {
             if i > 0 {
                 write!(f, ", ")?;
             }
-            write!(
-                f,
-                "{}[{}..{}]",
-                element.node(),
-                element.offset(),
-                element.limit()
-            )?;
+            match item {
+                PathItem::Node(element) => write!(
+                    f,
+                    "{}[{}..{}]",
+                    element.node(),
+                    element.offset(),
+                    element.limit()
+                )?,
+                PathItem::Gap(gap) => write!(f, "gap({gap})")?,
+            }
         }
 
         write!(f, "])")
@@ -0,0 +1,34 @@
+use crate::path::{GfaPathLength, LengthOverflowError};
+
+#[test]
+fn test_checked_arithmetic() {
+    let a = GfaPathLength::<u8>::from_usize(200);
+    let b = GfaPathLength::<u8>::from_usize(54);
+
+    assert_eq!(a.checked_add(b), Ok(GfaPathLength::from_usize(254)));
+    assert_eq!(a.checked_add(a), Err(LengthOverflowError));
+    // The maximum value is reserved and cannot be a length.
+    assert_eq!(
+        a.checked_add(GfaPathLength::from_usize(55)),
+        Err(LengthOverflowError),
+    );
+    assert_eq!(a.checked_sub(b), Ok(GfaPathLength::from_usize(146)));
+    assert_eq!(b.checked_sub(a), Err(LengthOverflowError));
+
+    assert_eq!(GfaPathLength::<u8>::checked_from_usize(254), Ok(254.into()));
+    assert_eq!(
+        GfaPathLength::<u8>::checked_from_usize(255),
+        Err(LengthOverflowError),
+    );
+    assert_eq!(
+        GfaPathLength::<u8>::checked_from_usize(1000),
+        Err(LengthOverflowError),
+    );
+}
+
+#[test]
+#[should_panic(expected = "length addition overflowed")]
+fn test_overflow_panics() {
+    let a = GfaPathLength::<u8>::from_usize(200);
+    let _ = a + a;
+}
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+
+use bidirected_adjacency_array::index::{DirectedNodeIndex, GraphIndexInteger};
+
+use crate::{
+    components::ComponentLabels,
+    dijkstra::shortest_path,
+    graph::SequenceGraph,
+    heap_size::{nested_vec_heap_size, vec_heap_size},
+    location::GfaLocation,
+    path::LengthOverflowError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The number of interval labels per strongly connected component.
+///
+/// Each label comes from a depth-first traversal of the condensation in a different order.
+/// More labels rule out more unreachable pairs without searching, but take more space.
+const INTERVAL_LABEL_COUNT: usize = 2;
+
+/// An index that answers whether one directed node can reach another, without storing distances.
+///
+/// The index is built over the condensation of the graph, i.e. the acyclic graph of its strongly connected components.
+/// Each component stores its topological rank and interval labels as in GRAIL (Yildirim et al., 2010),
+/// which rule out most unreachable pairs immediately.
+/// Pairs that are not ruled out are decided by a depth-first search over the condensation that is pruned with the same labels.
+pub struct ReachabilityIndex<IndexType> {
+    labels: ComponentLabels<IndexType>,
+    /// The successors of each strongly connected component in the condensation.
+    successors: Vec<Vec<usize>>,
+    /// The position of each strongly connected component in a topological order of the condensation.
+    ranks: Vec<usize>,
+    /// The intervals of each strongly connected component, as lowest and own post-order position.
+    intervals: Vec<[(usize, usize); INTERVAL_LABEL_COUNT]>,
+}
+
+impl<IndexType: GraphIndexInteger> ReachabilityIndex<IndexType> {
+    pub fn new(graph: &impl SequenceGraph<IndexType>) -> Self {
+        let labels = ComponentLabels::new(graph);
+        let component_count = labels.strong_component_count();
+
+        let mut successors = vec![Vec::new(); component_count];
+        for node in (0..2 * graph.node_count()).map(DirectedNodeIndex::from_usize) {
+            let component = labels.strong_component(node).into_usize();
+            for successor in graph.iter_successors(node) {
+                let successor = labels.strong_component(successor.node()).into_usize();
+                if successor != component {
+                    successors[component].push(successor);
+                }
+            }
+        }
+        for successors in &mut successors {
+            successors.sort_unstable();
+            successors.dedup();
+        }
+
+        let mut index = Self {
+            labels,
+            ranks: topological_ranks(&successors),
+            intervals: vec![[(0, 0); INTERVAL_LABEL_COUNT]; component_count],
+            successors,
+        };
+        for label in 0..INTERVAL_LABEL_COUNT {
+            index.compute_intervals(label);
+        }
+        index
+    }
+
+    /// Returns the approximate number of bytes that the index allocates on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.labels.heap_size()
+            + nested_vec_heap_size(&self.successors)
+            + vec_heap_size(&self.ranks)
+            + vec_heap_size(&self.intervals)
+    }
+
+    /// Returns true if a walk leads from the end of `source` to the end of `target`.
+    ///
+    /// Every node reaches itself with the empty walk.
+    pub fn can_reach_node(
+        &self,
+        source: DirectedNodeIndex<IndexType>,
+        target: DirectedNodeIndex<IndexType>,
+    ) -> bool {
+        let source = self.labels.strong_component(source).into_usize();
+        let target = self.labels.strong_component(target).into_usize();
+        if source == target {
+            return true;
+        }
+        if !self.may_reach(source, target) {
+            return false;
+        }
+
+        // Search the condensation, skipping components that are ruled out by their labels.
+        // The labels keep the search small, so the visited components are stored sparsely.
+        let mut visited = HashSet::from([source]);
+        let mut stack = vec![source];
+        while let Some(component) = stack.pop() {
+            for &successor in &self.successors[component] {
+                if successor == target {
+                    return true;
+                }
+                if self.may_reach(successor, target) && visited.insert(successor) {
+                    stack.push(successor);
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Returns true if [`shortest_path`] finds a path from `source` to `target`.
+    ///
+    /// If the locations are on the same node, or the target may lie within an overlap that a walk enters behind it,
+    /// this falls back to [`shortest_path`], since then the answer depends on the offsets and not only on the nodes.
+    /// Overlaps must not exceed the lengths of the nodes they enter.
+    /// Returns an error if the fallback search overflows the length type.
+    pub fn can_reach<LengthType: GraphIndexInteger>(
+        &self,
+        graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+        source: GfaLocation<IndexType, LengthType>,
+        target: GfaLocation<IndexType, LengthType>,
+    ) -> Result<bool, LengthOverflowError> {
+        if !self.labels.may_reach(source.node(), target.node())
+            || !self.can_reach_node(source.node(), target.node())
+        {
+            return Ok(false);
+        }
+
+        // Any walk that ends in the target node passes the target location if it enters the node before the location.
+        let is_hard = source.node() == target.node()
+            || graph
+                .iter_successors(graph.invert(target.node()))
+                .any(|predecessor| predecessor.tail_overlap() > target.offset().into_length());
+        Ok(!is_hard || shortest_path(graph, source, target)?.is_some())
+    }
+
+    /// Returns false if the labels prove that `source` cannot reach `target`.
+    fn may_reach(&self, source: usize, target: usize) -> bool {
+        self.ranks[source] < self.ranks[target]
+            && self.intervals[source]
+                .iter()
+                .zip(&self.intervals[target])
+                .all(|((source_low, source_post), (target_low, target_post))| {
+                    source_low <= target_low && target_post <= source_post
+                })
+    }
+
+    /// Compute one interval label for each component with a depth-first traversal.
+    ///
+    /// Even labels visit components in ascending order, and odd labels in descending order.
+    fn compute_intervals(&mut self, label: usize) {
+        let component_count = self.successors.len();
+        let is_ascending = label.is_multiple_of(2);
+        let order = |index: usize| {
+            if is_ascending {
+                index
+            } else {
+                component_count - 1 - index
+            }
+        };
+
+        let mut is_visited = vec![false; component_count];
+        let mut post_order = 0;
+        for root in (0..component_count).map(order) {
+            if is_visited[root] {
+                continue;
+            }
+            is_visited[root] = true;
+
+            // Each frame holds a component and the position of its next successor.
+            let mut stack = vec![(root, 0)];
+            while let Some((component, position)) = stack.last_mut() {
+                let component = *component;
+                let successors = &self.successors[component];
+                if *position < successors.len() {
+                    let successor = if is_ascending {
+                        successors[*position]
+                    } else {
+                        successors[successors.len() - 1 - *position]
+                    };
+                    *position += 1;
+                    if !is_visited[successor] {
+                        is_visited[successor] = true;
+                        stack.push((successor, 0));
+                    }
+                    continue;
+                }
+
+                stack.pop();
+                let low = successors
+                    .iter()
+                    .map(|successor| self.intervals[*successor][label].0)
+                    .fold(post_order, usize::min);
+                self.intervals[component][label] = (low, post_order);
+                post_order += 1;
+            }
+        }
+    }
+}
+
+/// Compute a topological order of an acyclic graph with Kahn's algorithm, and return the position of each node.
+fn topological_ranks(successors: &[Vec<usize>]) -> Vec<usize> {
+    let mut in_degrees = vec![0; successors.len()];
+    for successor in successors.iter().flatten() {
+        in_degrees[*successor] += 1;
+    }
+
+    let mut queue = (0..successors.len())
+        .filter(|node| in_degrees[*node] == 0)
+        .collect::<Vec<_>>();
+    let mut ranks = vec![0; successors.len()];
+    let mut rank = 0;
+    while let Some(node) = queue.pop() {
+        ranks[node] = rank;
+        rank += 1;
+        for successor in &successors[node] {
+            in_degrees[*successor] -= 1;
+            if in_degrees[*successor] == 0 {
+                queue.push(*successor);
+            }
+        }
+    }
+    ranks
+}
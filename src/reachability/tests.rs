@@ -0,0 +1,88 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    dijkstra::shortest_path,
+    graph::SequenceGraph,
+    location::{GfaLocation, GfaNodeOffset},
+    reachability::ReachabilityIndex,
+    test_util::random_graph,
+};
+
+#[test]
+fn test_random_graphs() {
+    // Sparse graphs have many strongly connected components, and dense graphs have few.
+    for (seed, edge_count) in (0..6).zip([8, 12, 16, 20, 24, 32]) {
+        let graph = random_graph(seed, 16, edge_count);
+        let index = ReachabilityIndex::new(&graph);
+        let locations = (0..2 * graph.node_count())
+            .map(DirectedNodeIndex::<u16>::from_usize)
+            .flat_map(|node| {
+                let length = graph.node_length(node).into_usize();
+                [0, length / 2, length]
+                    .map(|offset| GfaLocation::new(node, GfaNodeOffset::from_usize(offset)))
+            })
+            .collect::<Vec<_>>();
+
+        for source in &locations {
+            for target in &locations {
+                assert_eq!(
+                    index.can_reach(&graph, *source, *target).unwrap(),
+                    shortest_path(&graph, *source, *target).unwrap().is_some(),
+                    "Reachability differs from {source:?} to {target:?}",
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_chain() {
+    // A leads to B and B to C, so the reverse orientations lead from C to A.
+    let nodes = ["A", "B", "C"]
+        .map(|name| PlainGfaNodeData::new(name, "ACGT"))
+        .to_vec();
+    let edges = [(0, 2), (2, 4)].map(|(from, to)| {
+        BidirectedEdge::new_gfa(DirectedNodeIndex::new(from), DirectedNodeIndex::new(to), 1)
+    });
+    let graph: BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> =
+        BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges));
+    let index = ReachabilityIndex::new(&graph);
+
+    assert!(index.can_reach_node(0.into(), 4.into()));
+    assert!(index.can_reach_node(5.into(), 1.into()));
+    assert!(!index.can_reach_node(4.into(), 0.into()));
+    assert!(!index.can_reach_node(0.into(), 1.into()));
+
+    // The first base of C lies in the overlap with B, so it is behind the end of B.
+    assert!(
+        !index
+            .can_reach(
+                &graph,
+                GfaLocation::new(2.into(), 4.into()),
+                GfaLocation::new(4.into(), 0.into()),
+            )
+            .unwrap()
+    );
+    assert!(
+        index
+            .can_reach(
+                &graph,
+                GfaLocation::new(2.into(), 3.into()),
+                GfaLocation::new(4.into(), 0.into()),
+            )
+            .unwrap()
+    );
+    assert!(
+        index
+            .can_reach(
+                &graph,
+                GfaLocation::new(0.into(), 4.into()),
+                GfaLocation::new(4.into(), 1.into()),
+            )
+            .unwrap()
+    );
+}
@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use bidirected_adjacency_array::index::GraphIndexInteger;
+use spqr_tree::{
+    decomposition::{SPQRDecomposition, SPQRNodeType},
+    graph::StaticGraph,
+};
+
+use crate::{
+    block_cut_tree::BlockCutTree, contraction_hierarchy::ContractionHierarchy,
+    hub_labels::HubLabels, reachability::ReachabilityIndex,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Statistics about an SPQR decomposition, to judge how well a graph is suited for an SPQR-based index.
+///
+/// The statistics only describe the decomposition itself.
+/// This crate does not build an index over the decomposition, so there are no pole distances to report.
+/// The sizes and build times of the distance indices of this crate are reported by [`IndexStatistics`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecompositionStatistics {
+    node_count: usize,
+    component_count: usize,
+    block_count: usize,
+    cut_node_count: usize,
+    s_node_count: usize,
+    p_node_count: usize,
+    r_node_count: usize,
+    spqr_edge_count: usize,
+    skeleton_sizes: BTreeMap<usize, usize>,
+    largest_r_node: Option<(String, usize)>,
+}
+
+impl DecompositionStatistics {
+    pub fn new<Graph: StaticGraph>(decomposition: &SPQRDecomposition<'_, Graph>) -> Self {
+        let mut statistics = Self {
+            node_count: decomposition.iter_nodes().count(),
+            component_count: 0,
+            block_count: 0,
+            cut_node_count: 0,
+            s_node_count: 0,
+            p_node_count: 0,
+            r_node_count: 0,
+            spqr_edge_count: 0,
+            skeleton_sizes: BTreeMap::new(),
+            largest_r_node: None,
+        };
+
+        for (component_index, component) in decomposition.iter_components() {
+            statistics.component_count += 1;
+            statistics.cut_node_count += component.iter_cut_nodes().count();
+
+            for (block_index, _) in decomposition.iter_blocks_in_component(component_index) {
+                statistics.block_count += 1;
+                statistics.spqr_edge_count +=
+                    decomposition.iter_spqr_edges_in_block(block_index).count();
+
+                for (spqr_node_index, spqr_node) in
+                    decomposition.iter_spqr_nodes_in_block(block_index)
+                {
+                    let skeleton_size = spqr_node.iter_nodes().count();
+                    *statistics.skeleton_sizes.entry(skeleton_size).or_default() += 1;
+
+                    match spqr_node.spqr_node_type() {
+                        SPQRNodeType::SNode => statistics.s_node_count += 1,
+                        SPQRNodeType::PNode => statistics.p_node_count += 1,
+                        SPQRNodeType::RNode => {
+                            statistics.r_node_count += 1;
+                            if statistics
+                                .largest_r_node
+                                .as_ref()
+                                .is_none_or(|(_, largest_size)| skeleton_size > *largest_size)
+                            {
+                                statistics.largest_r_node = Some((
+                                    decomposition.spqr_node_name(spqr_node_index),
+                                    skeleton_size,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        statistics
+    }
+
+    /// The number of nodes of the decomposed graph.
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// The number of connected components.
+    pub fn component_count(&self) -> usize {
+        self.component_count
+    }
+
+    /// The number of biconnected components.
+    pub fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// The number of cut nodes in all block cut trees.
+    pub fn cut_node_count(&self) -> usize {
+        self.cut_node_count
+    }
+
+    pub fn s_node_count(&self) -> usize {
+        self.s_node_count
+    }
+
+    pub fn p_node_count(&self) -> usize {
+        self.p_node_count
+    }
+
+    pub fn r_node_count(&self) -> usize {
+        self.r_node_count
+    }
+
+    /// The number of edges in all SPQR trees.
+    pub fn spqr_edge_count(&self) -> usize {
+        self.spqr_edge_count
+    }
+
+    /// Maps each skeleton size, i.e. number of nodes in the skeleton of an SPQR node, to the number of SPQR nodes of that size.
+    pub fn skeleton_sizes(&self) -> &BTreeMap<usize, usize> {
+        &self.skeleton_sizes
+    }
+
+    /// The name and skeleton size of the R-node with the largest skeleton, if there are any R-nodes.
+    ///
+    /// R-nodes are the only SPQR nodes whose skeletons allow more than two routes between their poles,
+    /// so their size bounds the work per SPQR node.
+    pub fn largest_r_node(&self) -> Option<(&str, usize)> {
+        self.largest_r_node
+            .as_ref()
+            .map(|(name, size)| (name.as_str(), *size))
+    }
+}
+
+impl Display for DecompositionStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Nodes: {}", self.node_count)?;
+        writeln!(f, "Connected components: {}", self.component_count)?;
+        writeln!(f, "Blocks: {}", self.block_count)?;
+        writeln!(f, "Cut nodes: {}", self.cut_node_count)?;
+        writeln!(
+            f,
+            "SPQR nodes: {} S, {} P, {} R",
+            self.s_node_count, self.p_node_count, self.r_node_count,
+        )?;
+        writeln!(f, "SPQR edges: {}", self.spqr_edge_count)?;
+        match &self.largest_r_node {
+            Some((name, size)) => writeln!(f, "Largest R-node: {name} with {size} nodes")?,
+            None => writeln!(f, "Largest R-node: none")?,
+        }
+        writeln!(f, "Skeleton sizes:")?;
+        for (size, count) in &self.skeleton_sizes {
+            writeln!(f, "  {size}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The build time and size of one of the distance indices of this crate.
+///
+/// The statistics are taken from an index that the caller has built, together with the time the caller measured for building it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexStatistics {
+    name: &'static str,
+    build_time: Duration,
+    heap_size: usize,
+    precomputed_distance_count: usize,
+}
+
+impl IndexStatistics {
+    pub fn contraction_hierarchy<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+        hierarchy: &ContractionHierarchy<IndexType, LengthType>,
+        build_time: Duration,
+    ) -> Self {
+        Self {
+            name: "Contraction hierarchy",
+            build_time,
+            heap_size: hierarchy.heap_size(),
+            precomputed_distance_count: hierarchy.shortcut_count(),
+        }
+    }
+
+    pub fn hub_labels<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+        labels: &HubLabels<IndexType, LengthType>,
+        build_time: Duration,
+    ) -> Self {
+        Self {
+            name: "Hub labels",
+            build_time,
+            heap_size: labels.heap_size(),
+            precomputed_distance_count: labels.label_count(),
+        }
+    }
+
+    pub fn block_cut_tree<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+        tree: &BlockCutTree<IndexType, LengthType>,
+        build_time: Duration,
+    ) -> Self {
+        Self {
+            name: "Block-cut tree",
+            build_time,
+            heap_size: tree.heap_size(),
+            precomputed_distance_count: tree.turnaround_count(),
+        }
+    }
+
+    /// The reachability index stores no distances, so its precomputed distance count is zero.
+    pub fn reachability_index<IndexType: GraphIndexInteger>(
+        reachability: &ReachabilityIndex<IndexType>,
+        build_time: Duration,
+    ) -> Self {
+        Self {
+            name: "Reachability index",
+            build_time,
+            heap_size: reachability.heap_size(),
+            precomputed_distance_count: 0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn build_time(&self) -> Duration {
+        self.build_time
+    }
+
+    /// The approximate number of bytes that the index allocates on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.heap_size
+    }
+
+    /// The number of distances stored by the index, i.e. shortcuts, label entries or turnarounds.
+    pub fn precomputed_distance_count(&self) -> usize {
+        self.precomputed_distance_count
+    }
+}
+
+impl Display for IndexStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: built in {:.3} s, {} bytes, {} precomputed distances",
+            self.name,
+            self.build_time.as_secs_f64(),
+            self.heap_size,
+            self.precomputed_distance_count,
+        )
+    }
+}
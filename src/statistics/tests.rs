@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+use spqr_tree::io::plain_spqr_file::read_plain_spqr;
+
+use crate::{
+    block_cut_tree::BlockCutTree,
+    contraction_hierarchy::ContractionHierarchy,
+    hub_labels::HubLabels,
+    reachability::ReachabilityIndex,
+    statistics::{DecompositionStatistics, IndexStatistics},
+    test_util::random_graph,
+};
+
+#[test]
+fn test_statistics() {
+    // A complete graph on A, B, C and D, attached to the cycle D, E, F at D.
+    let nodes = ["A", "B", "C", "D", "E", "F"]
+        .map(|name| PlainGfaNodeData::new(name, "AC"))
+        .to_vec();
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 2),
+        (1, 3),
+        (2, 3),
+        (3, 4),
+        (4, 5),
+        (5, 3),
+    ]
+    .map(|(from, to)| {
+        BidirectedEdge::new_gfa(
+            DirectedNodeIndex::new(2 * from),
+            DirectedNodeIndex::new(2 * to),
+            0,
+        )
+    });
+    let graph: BidirectedAdjacencyArray<u8, _, PlainGfaEdgeData> =
+        BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges));
+
+    let spqr = "H v0.1 https://github.com/sebschmi/SPQR-tree-file-format
+G G0 A B C D E F
+B B0 G0 A B C D
+B B1 G0 D E F
+C D B0 B1
+R R0 B0 A B C D
+E E0 R0 B0 A B
+E E1 R0 B0 A C
+E E2 R0 B0 A D
+E E3 R0 B0 B C
+E E4 R0 B0 B D
+E E5 R0 B0 C D
+S S0 B1 D E F
+E E6 S0 B1 D E
+E E7 S0 B1 E F
+E E8 S0 B1 F D
+";
+    let decomposition = read_plain_spqr(&graph, &mut spqr.as_bytes()).unwrap();
+    let statistics = DecompositionStatistics::new(&decomposition);
+
+    assert_eq!(statistics.node_count(), 6);
+    assert_eq!(statistics.component_count(), 1);
+    assert_eq!(statistics.block_count(), 2);
+    assert_eq!(statistics.cut_node_count(), 1);
+    assert_eq!(
+        (
+            statistics.s_node_count(),
+            statistics.p_node_count(),
+            statistics.r_node_count(),
+        ),
+        (1, 0, 1),
+    );
+    assert_eq!(statistics.spqr_edge_count(), 0);
+    assert_eq!(statistics.largest_r_node(), Some(("R0", 4)));
+    assert_eq!(
+        statistics.skeleton_sizes().iter().collect::<Vec<_>>(),
+        [(&3, &1), (&4, &1)],
+    );
+    assert!(
+        statistics
+            .to_string()
+            .contains("SPQR nodes: 1 S, 0 P, 1 R\n")
+    );
+}
+
+#[test]
+fn test_index_statistics() {
+    let graph = random_graph(3, 30, 60);
+
+    let start = Instant::now();
+    let hierarchy = ContractionHierarchy::new(&graph);
+    let build_time = start.elapsed();
+    let statistics = IndexStatistics::contraction_hierarchy(&hierarchy, build_time);
+    assert_eq!(statistics.name(), "Contraction hierarchy");
+    assert_eq!(statistics.build_time(), build_time);
+    assert_eq!(statistics.heap_size(), hierarchy.heap_size());
+    assert_eq!(
+        statistics.precomputed_distance_count(),
+        hierarchy.shortcut_count()
+    );
+
+    let labels = HubLabels::new(&graph);
+    let statistics = IndexStatistics::hub_labels(&labels, Duration::from_millis(1500));
+    assert!(statistics.heap_size() > 0);
+    assert_eq!(
+        statistics.precomputed_distance_count(),
+        labels.label_count()
+    );
+    assert_eq!(
+        statistics.to_string(),
+        format!(
+            "Hub labels: built in 1.500 s, {} bytes, {} precomputed distances\n",
+            labels.heap_size(),
+            labels.label_count(),
+        ),
+    );
+
+    let tree = BlockCutTree::new(&graph).unwrap();
+    let statistics = IndexStatistics::block_cut_tree(&tree, Duration::ZERO);
+    assert!(statistics.heap_size() > 0);
+    assert_eq!(
+        statistics.precomputed_distance_count(),
+        tree.turnaround_count()
+    );
+
+    let reachability = ReachabilityIndex::new(&graph);
+    let statistics = IndexStatistics::reachability_index(&reachability, Duration::ZERO);
+    assert!(statistics.heap_size() > 0);
+    assert_eq!(statistics.precomputed_distance_count(), 0);
+}
@@ -0,0 +1,234 @@
+//! Extraction of small standalone graphs around paths and locations, e.g. for inspection in Bandage.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io::Write,
+};
+
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, GraphIndexInteger, NodeIndex},
+    io::gfa1::{GfaEdgeData, GfaNodeData, write_gfa1},
+};
+use binary_heap_plus::BinaryHeap;
+
+use crate::{
+    graph::{ClampedOverlaps, SequenceGraph},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, PathElement, PathItem},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A subgraph copied out of a larger graph, with a mapping back to the original node indices.
+pub struct ExtractedSubgraph<IndexType: GraphIndexInteger, NodeData, EdgeData> {
+    graph: BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+    /// The original node of each node of the subgraph.
+    original_nodes: Vec<NodeIndex<IndexType>>,
+    /// The extracted path, translated to the nodes of the subgraph.
+    path: Option<GfaPath<IndexType, u64>>,
+}
+
+impl<IndexType: GraphIndexInteger, NodeData: Clone, EdgeData: Clone>
+    ExtractedSubgraph<IndexType, NodeData, EdgeData>
+where
+    BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>:
+        SequenceGraph<IndexType, LengthType = u64>,
+{
+    /// Extract the nodes touched by the path, and all nodes that start within `context` bases before or after them.
+    ///
+    /// The path is kept and written as a `P` line by [`write_gfa1`](Self::write_gfa1).
+    pub fn from_path(
+        graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+        path: &GfaPath<IndexType, u64>,
+        context: usize,
+    ) -> Self {
+        let zero = GfaPathLength::from_usize(0);
+        let starts = path
+            .iter()
+            .flat_map(|element| [(element.node(), zero), (element.node().invert(), zero)]);
+        let mut subgraph = Self::from_nodes(graph, context_nodes(graph, starts, context));
+
+        let new_nodes = new_nodes(&subgraph.original_nodes);
+        let items = path
+            .iter_items()
+            .map(|item| match item {
+                PathItem::Node(element) => PathItem::Node(PathElement::new(
+                    translate(element.node(), &new_nodes),
+                    element.offset(),
+                    element.limit(),
+                )),
+                PathItem::Gap(gap) => PathItem::Gap(gap),
+            })
+            .collect();
+        subgraph.path = Some(GfaPath::from_items(items, path.length()));
+        subgraph
+    }
+
+    /// Extract all nodes that start within `radius` bases before or after the location.
+    pub fn from_location(
+        graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+        location: GfaLocation<IndexType, u64>,
+        radius: usize,
+    ) -> Self {
+        let node_length = graph.node_length(location.node());
+        // Costs are measured to the end of each start node, so the part of the node before the location is counted as well.
+        let starts = [
+            (
+                location.node(),
+                (node_length - location.offset()).into_length(),
+            ),
+            (location.node().invert(), location.offset().into_length()),
+        ];
+        Self::from_nodes(graph, context_nodes(graph, starts, radius))
+    }
+
+    fn from_nodes(
+        graph: &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData>,
+        original_nodes: BTreeSet<NodeIndex<IndexType>>,
+    ) -> Self {
+        let original_nodes = original_nodes.into_iter().collect::<Vec<_>>();
+        let new_nodes = new_nodes(&original_nodes);
+
+        let nodes = original_nodes
+            .iter()
+            .map(|node| graph.node_data(*node).clone())
+            .collect::<Vec<_>>();
+        let edges = graph
+            .iter_edges()
+            .map(|edge| graph.edge(edge))
+            .filter(|edge| {
+                new_nodes.contains_key(&edge.from().into_bidirected())
+                    && new_nodes.contains_key(&edge.to().into_bidirected())
+            })
+            .map(|edge| {
+                BidirectedEdge::new(
+                    translate(edge.from(), &new_nodes),
+                    translate(edge.to(), &new_nodes),
+                    edge.data().clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            graph: BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges)),
+            original_nodes,
+            path: None,
+        }
+    }
+}
+
+impl<IndexType: GraphIndexInteger, NodeData, EdgeData>
+    ExtractedSubgraph<IndexType, NodeData, EdgeData>
+{
+    pub fn graph(&self) -> &BidirectedAdjacencyArray<IndexType, NodeData, EdgeData> {
+        &self.graph
+    }
+
+    /// The extracted path in terms of the nodes of the subgraph, if the subgraph was extracted around a path.
+    pub fn path(&self) -> Option<&GfaPath<IndexType, u64>> {
+        self.path.as_ref()
+    }
+
+    /// Map a node of the subgraph to the same orientation of its node in the original graph.
+    pub fn original_node(
+        &self,
+        node: DirectedNodeIndex<IndexType>,
+    ) -> DirectedNodeIndex<IndexType> {
+        DirectedNodeIndex::from_bidirected(
+            self.original_nodes[node.into_bidirected().into_usize()],
+            node.is_forward(),
+        )
+    }
+
+    /// Write the subgraph in GFA 1.0 format.
+    ///
+    /// If the subgraph was extracted around a path, the path is written as a `P` line with the given name.
+    /// Gaps in the path are written as jumps, i.e. the segments around them are separated by `;` instead of `,`.
+    pub fn write_gfa1(&self, path_name: &str, writer: &mut impl Write) -> std::io::Result<()>
+    where
+        NodeData: GfaNodeData,
+        EdgeData: GfaEdgeData,
+    {
+        write_gfa1(&self.graph, writer)?;
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let mut segments = String::new();
+        let mut separator = "";
+        for item in path.iter_items() {
+            match item {
+                PathItem::Node(element) => {
+                    let node = element.node();
+                    segments.push_str(separator);
+                    segments.push_str(&self.graph.node_data(node.into_bidirected()).name());
+                    segments.push(if node.is_forward() { '+' } else { '-' });
+                    separator = ",";
+                }
+                PathItem::Gap(_) => separator = ";",
+            }
+        }
+        writeln!(writer, "P\t{path_name}\t{segments}\t*")
+    }
+}
+
+/// Collect the start nodes and all nodes that start less than `limit` bases after the end of a start node.
+///
+/// Each start node is given with the cost already used up at its end.
+fn context_nodes<IndexType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = u64>,
+    starts: impl IntoIterator<Item = (DirectedNodeIndex<IndexType>, GfaPathLength<u64>)>,
+    limit: usize,
+) -> BTreeSet<NodeIndex<IndexType>> {
+    let graph = ClampedOverlaps::new(graph);
+    let limit = GfaPathLength::from_usize(limit);
+    let mut open_list = BinaryHeap::new_min();
+    let mut closed_list = HashSet::new();
+    let mut nodes = BTreeSet::new();
+    for (node, cost) in starts {
+        nodes.insert(node.into_bidirected());
+        open_list.push((cost, node));
+    }
+
+    while let Some((cost, node)) = open_list.pop() {
+        if !closed_list.insert(node) {
+            continue;
+        }
+        nodes.insert(node.into_bidirected());
+
+        for successor in graph.iter_successors(node) {
+            // A cost that overflows exceeds the limit, so the node is not part of the context.
+            if let Ok(start_cost) =
+                cost.checked_add(successor.gap().unwrap_or(GfaPathLength::from_usize(0)))
+                && start_cost < limit
+                && !closed_list.contains(&successor.node())
+                && let Ok(successor_cost) = successor.extend_cost(cost, &graph)
+            {
+                open_list.push((successor_cost, successor.node()));
+            }
+        }
+    }
+
+    nodes
+}
+
+/// Map each original node to its node in the subgraph.
+fn new_nodes<IndexType: GraphIndexInteger>(
+    original_nodes: &[NodeIndex<IndexType>],
+) -> HashMap<NodeIndex<IndexType>, NodeIndex<IndexType>> {
+    original_nodes
+        .iter()
+        .enumerate()
+        .map(|(new_node, original_node)| (*original_node, NodeIndex::from_usize(new_node)))
+        .collect()
+}
+
+fn translate<IndexType: GraphIndexInteger>(
+    node: DirectedNodeIndex<IndexType>,
+    new_nodes: &HashMap<NodeIndex<IndexType>, NodeIndex<IndexType>>,
+) -> DirectedNodeIndex<IndexType> {
+    DirectedNodeIndex::from_bidirected(new_nodes[&node.into_bidirected()], node.is_forward())
+}
@@ -0,0 +1,116 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::DirectedNodeIndex,
+    io::gfa1::{GfaNodeData, PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    io::{LengthMismatchPolicy, UnknownOverlapPolicy, read_gfa1_lengths},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, PathElement},
+    subgraph::ExtractedSubgraph,
+};
+
+/// The chain A, B, C, D, E, with F attached to the start of A.
+fn graph() -> BidirectedAdjacencyArray<u8, PlainGfaNodeData, PlainGfaEdgeData> {
+    let nodes = vec![
+        PlainGfaNodeData::new("A", "AAAA"),
+        PlainGfaNodeData::new("B", "BBB"),
+        PlainGfaNodeData::new("C", "CCCCC"),
+        PlainGfaNodeData::new("D", "DD"),
+        PlainGfaNodeData::new("E", "EEEE"),
+        PlainGfaNodeData::new("F", "F"),
+    ];
+    let edges = [(0, 2), (2, 4), (4, 6), (6, 8), (10, 0)].map(|(from, to)| {
+        BidirectedEdge::new_gfa(DirectedNodeIndex::new(from), DirectedNodeIndex::new(to), 0)
+    });
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+fn node_names(subgraph: &ExtractedSubgraph<u8, PlainGfaNodeData, PlainGfaEdgeData>) -> String {
+    let graph = subgraph.graph();
+    graph
+        .iter_nodes()
+        .map(|node| graph.node_data(node).name().into_owned())
+        .collect()
+}
+
+#[test]
+fn test_from_path() {
+    let graph = graph();
+    let path = GfaPath::new(
+        vec![
+            PathElement::new(2.into(), 0.into(), 3.into()),
+            PathElement::new(4.into(), 0.into(), 2.into()),
+        ],
+        GfaPathLength::from_usize(5),
+    );
+
+    let subgraph = ExtractedSubgraph::from_path(&graph, &path, 0);
+    let mut buffer = Vec::new();
+    subgraph.write_gfa1("query", &mut buffer).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "H\tVN:Z:1.0\nS\tB\tBBB\nS\tC\tCCCCC\nL\tB\t+\tC\t+\t0M\nP\tquery\tB+,C+\t*\n",
+    );
+    assert_eq!(
+        subgraph
+            .path()
+            .unwrap()
+            .iter()
+            .map(|element| subgraph.original_node(element.node()))
+            .collect::<Vec<_>>(),
+        [2.into(), 4.into()],
+    );
+
+    // One base of context reaches the neighbours of the path, and three bases reach past D into E.
+    assert_eq!(
+        node_names(&ExtractedSubgraph::from_path(&graph, &path, 1)),
+        "ABCD"
+    );
+    assert_eq!(
+        node_names(&ExtractedSubgraph::from_path(&graph, &path, 3)),
+        "ABCDE"
+    );
+    assert_eq!(
+        node_names(&ExtractedSubgraph::from_path(&graph, &path, 5)),
+        "ABCDEF"
+    );
+}
+
+#[test]
+fn test_from_location() {
+    let graph = graph();
+    let location = GfaLocation::new(4.into(), 1.into());
+
+    let subgraph = ExtractedSubgraph::from_location(&graph, location, 2);
+    assert!(subgraph.path().is_none());
+    assert_eq!(node_names(&subgraph), "BC");
+    assert_eq!(subgraph.original_node(1.into()), 3.into());
+
+    assert_eq!(
+        node_names(&ExtractedSubgraph::from_location(&graph, location, 5)),
+        "ABCD",
+    );
+}
+
+#[test]
+fn test_from_location_across_huge_gap() {
+    // The gap from A to B is so long that the cost of reaching B overflows.
+    let gfa = "S\tA\t*\tLN:i:4
+S\tB\t*\tLN:i:2
+S\tC\t*\tLN:i:3
+J\tA\t+\tB\t+\t18446744073709551614
+L\tA\t+\tC\t+\t0M
+";
+    let graph = read_gfa1_lengths::<u8>(
+        &mut gfa.as_bytes(),
+        LengthMismatchPolicy::Error,
+        UnknownOverlapPolicy::Error,
+    )
+    .unwrap();
+    let subgraph =
+        ExtractedSubgraph::from_location(&graph, GfaLocation::new(0.into(), 2.into()), 5);
+    assert_eq!(subgraph.graph().node_count(), 2);
+    assert_eq!(subgraph.original_node(2.into()), 4.into());
+}
@@ -0,0 +1,81 @@
+use bidirected_adjacency_array::{
+    graph::{BidirectedAdjacencyArray, BidirectedEdge},
+    index::{DirectedNodeIndex, GraphIndexInteger},
+    io::gfa1::{PlainGfaEdgeData, PlainGfaNodeData},
+};
+
+use crate::{
+    dijkstra::shortest_path,
+    graph::SequenceGraph,
+    location::{GfaLocation, GfaNodeOffset},
+    path::GfaPathLength,
+};
+
+/// Build a pseudo-random graph with the given number of nodes and edges.
+///
+/// Overlaps never exceed the lengths of the nodes they connect.
+pub(crate) fn random_graph(
+    seed: u64,
+    node_count: usize,
+    edge_count: usize,
+) -> BidirectedAdjacencyArray<u16, PlainGfaNodeData, PlainGfaEdgeData> {
+    let mut state = seed;
+    let mut next = |limit: usize| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize % limit
+    };
+
+    let lengths = (0..node_count).map(|_| 1 + next(8)).collect::<Vec<_>>();
+    let nodes = lengths
+        .iter()
+        .enumerate()
+        .map(|(index, length)| PlainGfaNodeData::new(index.to_string(), "A".repeat(*length)))
+        .collect::<Vec<_>>();
+    let edges = (0..edge_count)
+        .map(|_| {
+            let from = next(2 * node_count);
+            let to = next(2 * node_count);
+            let max_overlap = lengths[from / 2].min(lengths[to / 2]);
+            BidirectedEdge::new_gfa(
+                DirectedNodeIndex::new(from as u16),
+                DirectedNodeIndex::new(to as u16),
+                next(max_overlap + 1) as u16,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    BidirectedAdjacencyArray::new(nodes.into(), FromIterator::from_iter(edges))
+}
+
+/// Compare the distances between all pairs of node ends against the search without an index.
+///
+/// The distances of the index under test are computed by `distance`.
+pub(crate) fn assert_same_distances<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    mut distance: impl FnMut(
+        GfaLocation<IndexType, LengthType>,
+        GfaLocation<IndexType, LengthType>,
+    ) -> Option<GfaPathLength<LengthType>>,
+) {
+    let locations = (0..2 * graph.node_count())
+        .map(DirectedNodeIndex::<IndexType>::from_usize)
+        .flat_map(|node| {
+            let length = graph.node_length(node).into_usize();
+            [0, length].map(|offset| GfaLocation::new(node, GfaNodeOffset::from_usize(offset)))
+        })
+        .collect::<Vec<_>>();
+
+    for source in &locations {
+        for target in &locations {
+            assert_eq!(
+                distance(*source, *target),
+                shortest_path(graph, *source, *target)
+                    .unwrap()
+                    .map(|path| path.length()),
+                "Distances differ from {source:?} to {target:?}",
+            );
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use bidirected_adjacency_array::index::GraphIndexInteger;
+
+use crate::{
+    dijkstra::{Label, backtrack_walk, saturation_thresholds, search_target},
+    graph::{SequenceGraph, Successor},
+    location::GfaLocation,
+    path::{GfaPath, GfaPathLength, LengthOverflowError},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The result of a shortest path query through a sequence of waypoints.
+pub struct WaypointPath<IndexType, LengthType> {
+    path: GfaPath<IndexType, LengthType>,
+    leg_lengths: Vec<GfaPathLength<LengthType>>,
+}
+
+/// Find the shortest path that visits the given waypoints in order.
+///
+/// The path is the concatenation of the shortest paths between consecutive waypoints.
+/// The legs are searched one after another with one search per leg:
+/// once a leg reaches its waypoint, the label that reached it is the seed of the search for the next leg,
+/// so the labels of all legs form one walk from the first waypoint to the last one.
+/// Legs between equal pairs of waypoints, as they occur when a read loops through a cycle, are only searched once,
+/// and their walk is repeated instead.
+/// Returns `None` if there are no waypoints or if any leg is unreachable,
+/// and an error if the length of the path does not fit into the length type.
+pub fn waypoint_shortest_path<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    graph: &impl SequenceGraph<IndexType, LengthType = LengthType>,
+    waypoints: &[GfaLocation<IndexType, LengthType>],
+) -> Result<Option<WaypointPath<IndexType, LengthType>>, LengthOverflowError> {
+    let Some(first) = waypoints.first() else {
+        return Ok(None);
+    };
+
+    // Costs of each leg are measured from the waypoint where it starts to the end of the node of a label.
+    let mut labels = vec![Label {
+        entry: Successor::new(first.node(), GfaPathLength::from_usize(0)),
+        predecessor: None,
+        seed_index: 0,
+    }];
+    let mut seed_label = 0;
+    let mut seed_cost = graph
+        .node_length(first.node())
+        .checked_sub(first.offset().into_length())?;
+    let mut length = GfaPathLength::from_usize(0);
+    let mut leg_lengths = Vec::with_capacity(waypoints.len() - 1);
+    // Maps each searched leg to the labels where it starts and ends.
+    let mut leg_cache = HashMap::new();
+    let mut saturation_threshold_cache = HashMap::new();
+
+    for leg in waypoints.windows(2) {
+        let (source, target) = (leg[0], leg[1]);
+        let target_threshold = graph
+            .node_length(target.node())
+            .checked_sub(target.offset().into_length())?;
+
+        let (label_index, leg_length) =
+            if let Some(&(start_label, end_label, leg_length)) = leg_cache.get(&(source, target)) {
+                (
+                    repeat_walk(&mut labels, seed_label, start_label, end_label),
+                    leg_length,
+                )
+            } else {
+                let saturation_thresholds = match saturation_threshold_cache.entry(target) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(saturation_thresholds(
+                        graph,
+                        [(target.node(), target_threshold)],
+                    )?),
+                };
+                let Some((label_index, cost)) = search_target(
+                    graph,
+                    &mut labels,
+                    (seed_label, seed_cost),
+                    (target.node(), target_threshold),
+                    saturation_thresholds,
+                )?
+                else {
+                    return Ok(None);
+                };
+                let leg_length = cost - target_threshold;
+                leg_cache.insert((source, target), (seed_label, label_index, leg_length));
+                (label_index, leg_length)
+            };
+
+        leg_lengths.push(leg_length);
+        length = length.checked_add(leg_length)?;
+        seed_label = label_index;
+        // The next leg starts at the target, which lies this far before the end of its node.
+        seed_cost = target_threshold;
+    }
+
+    let path = GfaPath::from_walk(
+        backtrack_walk(&labels, seed_label),
+        first.offset(),
+        length,
+        graph,
+    );
+    Ok(Some(WaypointPath { path, leg_lengths }))
+}
+
+/// Append the walk from `start_label` to `end_label` to the walk ending at `seed_label`.
+///
+/// Returns the label where the appended walk ends.
+fn repeat_walk<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>(
+    labels: &mut Vec<Label<IndexType, LengthType>>,
+    seed_label: usize,
+    start_label: usize,
+    end_label: usize,
+) -> usize {
+    let mut entries = Vec::new();
+    let mut current = end_label;
+    while current != start_label {
+        let label = &labels[current];
+        entries.push(label.entry);
+        current = label.predecessor.unwrap();
+    }
+
+    let mut label_index = seed_label;
+    for entry in entries.into_iter().rev() {
+        labels.push(Label {
+            entry,
+            predecessor: Some(label_index),
+            seed_index: 0,
+        });
+        label_index = labels.len() - 1;
+    }
+    label_index
+}
+
+impl<IndexType: GraphIndexInteger, LengthType: GraphIndexInteger>
+    WaypointPath<IndexType, LengthType>
+{
+    pub fn path(&self) -> &GfaPath<IndexType, LengthType> {
+        &self.path
+    }
+
+    pub fn into_path(self) -> GfaPath<IndexType, LengthType> {
+        self.path
+    }
+
+    /// Returns the lengths of the shortest paths between consecutive waypoints.
+    pub fn leg_lengths(&self) -> &[GfaPathLength<LengthType>] {
+        &self.leg_lengths
+    }
+}
@@ -0,0 +1,113 @@
+use bidirected_adjacency_array::io::gfa1::read_gfa1;
+
+use crate::{location::GfaLocation, path::PathElement, waypoint::waypoint_shortest_path};
+
+const CYCLE_GFA: &str = "H\tVN:Z:1.0
+S\tA\tAAA
+S\tB\tCCC
+S\tC\tGGGG
+L\tA\t+\tB\t+\t0M
+L\tB\t+\tC\t+\t1M
+L\tC\t+\tA\t+\t0M
+";
+
+#[test]
+fn test_waypoints() {
+    let graph = read_gfa1::<u8>(&mut CYCLE_GFA.as_bytes()).unwrap();
+    let waypoints = [
+        GfaLocation::new(0.into(), 1.into()),
+        GfaLocation::new(2.into(), 1.into()),
+        GfaLocation::new(2.into(), 2.into()),
+        GfaLocation::new(4.into(), 3.into()),
+    ];
+
+    let result = waypoint_shortest_path(&graph, &waypoints).unwrap().unwrap();
+    assert_eq!(
+        result
+            .leg_lengths()
+            .iter()
+            .map(|length| length.into_usize())
+            .collect::<Vec<_>>(),
+        [3, 1, 3],
+    );
+    assert_eq!(result.path().length().into_usize(), 7);
+    assert_eq!(
+        result.path().iter().collect::<Vec<_>>(),
+        [
+            PathElement::new(0.into(), 1.into(), 3.into()),
+            PathElement::new(2.into(), 0.into(), 3.into()),
+            PathElement::new(4.into(), 1.into(), 3.into()),
+        ],
+    );
+}
+
+#[test]
+fn test_waypoints_around_cycle() {
+    let graph = read_gfa1::<u8>(&mut CYCLE_GFA.as_bytes()).unwrap();
+    let a = GfaLocation::new(0.into(), 1.into());
+    let b = GfaLocation::new(2.into(), 1.into());
+
+    let result = waypoint_shortest_path(&graph, &[a, b, a, b])
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        result
+            .leg_lengths()
+            .iter()
+            .map(|length| length.into_usize())
+            .collect::<Vec<_>>(),
+        [3, 6, 3],
+    );
+    assert_eq!(result.path().length().into_usize(), 12);
+    assert_eq!(
+        result.path().iter().next(),
+        Some(PathElement::new(0.into(), 1.into(), 3.into()))
+    );
+    assert_eq!(
+        result.path().iter().last(),
+        Some(PathElement::new(2.into(), 0.into(), 1.into()))
+    );
+}
+
+#[test]
+fn test_waypoints_unreachable() {
+    let graph = read_gfa1::<u8>(&mut CYCLE_GFA.as_bytes()).unwrap();
+    let waypoints = [
+        GfaLocation::new(0.into(), 1.into()),
+        GfaLocation::new(1.into(), 1.into()),
+    ];
+
+    assert!(
+        waypoint_shortest_path(&graph, &waypoints)
+            .unwrap()
+            .is_none()
+    );
+    assert!(waypoint_shortest_path(&graph, &[]).unwrap().is_none());
+}
+
+#[test]
+fn test_waypoints_backwards_in_node() {
+    let graph = read_gfa1::<u8>(&mut CYCLE_GFA.as_bytes()).unwrap();
+    let waypoints = [
+        GfaLocation::new(2.into(), 2.into()),
+        GfaLocation::new(2.into(), 1.into()),
+    ];
+
+    let result = waypoint_shortest_path(&graph, &waypoints).unwrap().unwrap();
+    assert_eq!(result.leg_lengths()[0].into_usize(), 8);
+    assert_eq!(result.path().length().into_usize(), 8);
+    assert_eq!(
+        result.path().iter().next(),
+        Some(PathElement::new(2.into(), 2.into(), 3.into()))
+    );
+    assert_eq!(
+        result.path().iter().last(),
+        Some(PathElement::new(2.into(), 0.into(), 1.into()))
+    );
+
+    let result = waypoint_shortest_path(&graph, &waypoints[..1])
+        .unwrap()
+        .unwrap();
+    assert!(result.leg_lengths().is_empty());
+    assert_eq!(result.path().length().into_usize(), 0);
+}